@@ -0,0 +1,78 @@
+//! Walks the chain back from the head of a sync proof to an older header, inside the zkVM.
+//!
+//! Headers are linked by `last_block_id`, so no signatures need to be checked: every header is
+//! authenticated by the hash its successor commits to.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use sha2::{Sha256, Digest};
+use cryptographic_sync_common::{AncestryPublicValues, SyncPublicValues, Timestamp, VerifiedHead};
+use tendermint::{block::Header, Hash};
+
+/// A SHA-256 tendermint hash as a fixed-size array.
+fn hash_bytes(hash: &Hash) -> [u8; 32] {
+    hash.as_bytes().try_into().expect("hash is not 32 bytes")
+}
+
+/// The fields of a header that are committed for the ancestor.
+fn verified_head(header: &Header) -> VerifiedHead {
+    VerifiedHead {
+        hash: hash_bytes(&header.hash()),
+        height: header.height.value(),
+        time: Timestamp::from_unix_nanos(header.time.unix_timestamp_nanos()),
+        chain_id: header.chain_id.to_string(),
+        data_hash: header.data_hash.as_ref().map(hash_bytes),
+        app_hash: header.app_hash.as_bytes().to_vec(),
+        next_validators_hash: hash_bytes(&header.next_validators_hash),
+    }
+}
+
+pub fn main() {
+    // The verifying key of the sync program, and the public values of its proof
+    let sync_vkey: [u32; 8] = sp1_zkvm::io::read();
+    let byte_slice: &[u8] = unsafe {
+        core::slice::from_raw_parts(sync_vkey.as_ptr() as *const u8, sync_vkey.len() * core::mem::size_of::<u32>())
+    };
+    let sync_vkey_hash: [u8; 32] = Sha256::digest(byte_slice).into();
+    let sync_public_values: Vec<u8> = sp1_zkvm::io::read();
+    let sync_public_values_digest: [u8; 32] = Sha256::digest(&sync_public_values).into();
+
+    // The headers from the sync head back to the requested height, newest first
+    let headers_bytes = sp1_zkvm::io::read_vec();
+    let headers: Vec<Header> = serde_cbor::from_slice(&headers_bytes).expect("couldn't deserialize headers");
+
+    let sync = SyncPublicValues::decode(&sync_public_values).expect("couldn't decode sync public values");
+    // Ensure that the sync proof is of the given circuit, and valid
+    if sync.vkey_hash != sync_vkey_hash || !sync.is_valid() {
+        panic!("not valid!");
+    }
+    sp1_zkvm::lib::verify::verify_sp1_proof(&sync_vkey, &sync_public_values_digest);
+
+    // Ensure that the walk starts at the verified head
+    let head = headers.first().expect("expected at least one header");
+    if hash_bytes(&head.hash()) != sync.head.hash {
+        panic!("expected the first header to be the sync head");
+    }
+
+    // Ensure that every header is the parent of the one before it
+    for pair in headers.windows(2) {
+        let (child, parent) = (&pair[0], &pair[1]);
+        let last_block_id = child.last_block_id.as_ref().expect("expected the header to have a parent");
+        if last_block_id.hash != parent.hash() {
+            panic!("expected the header hash to match its child's last_block_id");
+        }
+        if parent.height.increment() != child.height || parent.chain_id != child.chain_id {
+            panic!("expected the parent to be the previous block on the same chain");
+        }
+    }
+
+    let ancestor = verified_head(headers.last().unwrap());
+    sp1_zkvm::io::commit(&AncestryPublicValues::new(
+        sync_vkey_hash,
+        sync_public_values_digest,
+        sync.start,
+        sync.head.chain_id,
+        sync.head.hash,
+        ancestor,
+    ));
+}
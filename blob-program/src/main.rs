@@ -0,0 +1,53 @@
+//! Proves that a blob is included in the head of a sync proof, inside the zkVM.
+//!
+//! The head's `data_hash` is the data root of its block, which commits to the roots of every row
+//! of the extended data square. The blob's shares are proven to be in the rows they span, and
+//! those rows to be under the data root, so the blob is available wherever the head is trusted.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use sha2::{Sha256, Digest};
+use cryptographic_sync_common::{BlobInclusionProof, BlobInclusionPublicValues, SyncPublicValues};
+
+pub fn main() {
+    // The verifying key of the sync program, and the public values of its proof
+    let sync_vkey: [u32; 8] = sp1_zkvm::io::read();
+    let byte_slice: &[u8] = unsafe {
+        core::slice::from_raw_parts(sync_vkey.as_ptr() as *const u8, sync_vkey.len() * core::mem::size_of::<u32>())
+    };
+    let sync_vkey_hash: [u8; 32] = Sha256::digest(byte_slice).into();
+    let sync_public_values: Vec<u8> = sp1_zkvm::io::read();
+    let sync_public_values_digest: [u8; 32] = Sha256::digest(&sync_public_values).into();
+
+    // The namespace and shares of the blob, with their row and share proofs
+    let blob: BlobInclusionProof = sp1_zkvm::io::read();
+
+    let sync = SyncPublicValues::decode(&sync_public_values).expect("couldn't decode sync public values");
+    // Ensure that the sync proof is of the given circuit, and valid
+    if sync.vkey_hash != sync_vkey_hash || !sync.is_valid() {
+        panic!("not valid!");
+    }
+    sp1_zkvm::lib::verify::verify_sp1_proof(&sync_vkey, &sync_public_values_digest);
+
+    // Ensure that the shares are a whole blob under the head's data root
+    let data_hash = sync.head.data_hash.expect("expected the sync head to have a data hash");
+    // When the sync proof committed the head's data availability header, the rows must be of its square
+    if let Some(dah) = &sync.head_dah {
+        if blob.rows.iter().any(|row| row.row_proof.total != 2 * dah.square_size) {
+            panic!("rows are not of the head's data square");
+        }
+    }
+    let commitment = blob.verify(&data_hash).unwrap_or_else(|e| panic!("{}", e));
+
+    sp1_zkvm::io::commit(&BlobInclusionPublicValues::new(
+        sync_vkey_hash,
+        sync_public_values_digest,
+        sync.start,
+        sync.head.chain_id,
+        sync.head.hash,
+        sync.head.height,
+        data_hash,
+        blob.namespace,
+        commitment,
+    ));
+}
@@ -0,0 +1,54 @@
+use crate::public_values::decode_versioned;
+use crate::{Checkpoint, DecodeError, VerifiedHead};
+use serde::{Deserialize, Serialize};
+
+/// Version of the [`AncestryPublicValues`] layout.
+pub const ANCESTRY_PUBLIC_VALUES_VERSION: u32 = 2;
+
+/// The values committed by the ancestry program.
+///
+/// They attest that `ancestor` is an ancestor of the head of a sync proof, reached by following
+/// `last_block_id` back from that head one block at a time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AncestryPublicValues {
+    /// Layout version, always the first field so it can be read on its own.
+    pub version: u32,
+    /// SHA-256 of the verifying key words of the sync program whose proof was verified.
+    pub sync_vkey_hash: [u8; 32],
+    /// SHA-256 of the public values of the verified sync proof.
+    pub sync_public_values_digest: [u8; 32],
+    /// The trusted checkpoint the sync proof starts from, which the ancestry rests on.
+    pub start: Checkpoint,
+    /// Chain ID of the sync proof's head.
+    pub chain_id: String,
+    /// Hash of the head of the sync proof the chain was walked back from.
+    pub head_hash: [u8; 32],
+    /// The older header that was reached.
+    pub ancestor: VerifiedHead,
+}
+
+impl AncestryPublicValues {
+    pub fn new(
+        sync_vkey_hash: [u8; 32],
+        sync_public_values_digest: [u8; 32],
+        start: Checkpoint,
+        chain_id: String,
+        head_hash: [u8; 32],
+        ancestor: VerifiedHead,
+    ) -> Self {
+        Self {
+            version: ANCESTRY_PUBLIC_VALUES_VERSION,
+            sync_vkey_hash,
+            sync_public_values_digest,
+            start,
+            chain_id,
+            head_hash,
+            ancestor,
+        }
+    }
+
+    /// Decode public values committed by the ancestry program, checking the layout version first.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        decode_versioned(bytes, ANCESTRY_PUBLIC_VALUES_VERSION)
+    }
+}
@@ -0,0 +1,556 @@
+use crate::public_values::decode_versioned;
+use crate::{
+    merkle_root, namespaced_leaf, nmt_root, Checkpoint, DecodeError, MerkleProof, NamespacedHash,
+    NmtRangeProof, NAMESPACE_SIZE,
+};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Version of the [`BlobInclusionPublicValues`] layout.
+pub const BLOB_PUBLIC_VALUES_VERSION: u32 = 2;
+
+/// Size of a share.
+pub const SHARE_SIZE: usize = 512;
+
+/// Blobs are split into subtrees of at most this many shares' width when committed to.
+pub const SUBTREE_ROOT_THRESHOLD: usize = 64;
+
+/// The values committed by the blob inclusion program.
+///
+/// They attest that a blob of `namespace` with the given commitment is included in the block
+/// whose `data_hash` is committed by the head of a sync proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobInclusionPublicValues {
+    /// Layout version, always the first field so it can be read on its own.
+    pub version: u32,
+    /// SHA-256 of the verifying key words of the sync program whose proof was verified.
+    pub sync_vkey_hash: [u8; 32],
+    /// SHA-256 of the public values of the verified sync proof.
+    pub sync_public_values_digest: [u8; 32],
+    /// The trusted checkpoint the sync proof starts from, which the inclusion rests on.
+    pub start: Checkpoint,
+    /// Chain ID of the sync proof's head.
+    pub chain_id: String,
+    /// Hash of the head of the sync proof, the block the blob is included in.
+    pub head_hash: [u8; 32],
+    pub height: u64,
+    /// The head's data root the blob was verified against.
+    pub data_hash: [u8; 32],
+    /// Namespace of the blob, its version byte followed by its ID.
+    pub namespace: Vec<u8>,
+    /// The blob's share commitment, as included in its `MsgPayForBlobs`.
+    pub commitment: [u8; 32],
+}
+
+impl BlobInclusionPublicValues {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        sync_vkey_hash: [u8; 32],
+        sync_public_values_digest: [u8; 32],
+        start: Checkpoint,
+        chain_id: String,
+        head_hash: [u8; 32],
+        height: u64,
+        data_hash: [u8; 32],
+        namespace: Vec<u8>,
+        commitment: [u8; 32],
+    ) -> Self {
+        Self {
+            version: BLOB_PUBLIC_VALUES_VERSION,
+            sync_vkey_hash,
+            sync_public_values_digest,
+            start,
+            chain_id,
+            head_hash,
+            height,
+            data_hash,
+            namespace,
+            commitment,
+        }
+    }
+
+    /// Decode public values committed by the blob inclusion program, checking the layout version
+    /// first.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        decode_versioned(bytes, BLOB_PUBLIC_VALUES_VERSION)
+    }
+}
+
+/// The shares of a blob in one row of the original data square, and their proofs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowInclusionProof {
+    /// The row's namespaced Merkle tree root.
+    pub row_root: Vec<u8>,
+    /// Proof of the row root among the roots the data root commits to.
+    pub row_proof: MerkleProof,
+    /// Proof of the blob's shares in the row.
+    pub share_proof: NmtRangeProof,
+}
+
+/// The shares of a blob, with proofs of their inclusion under a block's data root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobInclusionProof {
+    pub namespace: Vec<u8>,
+    /// Every share of the blob, in order.
+    pub shares: Vec<Vec<u8>>,
+    /// The rows the shares are in, in order.
+    pub rows: Vec<RowInclusionProof>,
+}
+
+/// Why a blob inclusion proof does not verify.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlobInclusionError {
+    /// The namespace is not of the namespace size.
+    MalformedNamespace,
+    /// A share is not of the share size, or not in the blob's namespace.
+    MalformedShare { index: usize },
+    /// The shares do not start a sequence, or there are not as many as its length needs.
+    IncompleteBlob,
+    /// The rows do not cover every share, are not consecutive rows of the original data, or
+    /// skip shares between them.
+    MismatchedRows,
+    /// The shares of a row are not included in its root.
+    InvalidShareProof { row: u64 },
+    /// A row root is not included in the data root.
+    InvalidRowProof { row: u64 },
+}
+
+impl fmt::Display for BlobInclusionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobInclusionError::MalformedNamespace => write!(f, "malformed namespace"),
+            BlobInclusionError::MalformedShare { index } => {
+                write!(f, "share {} is malformed or of another namespace", index)
+            }
+            BlobInclusionError::IncompleteBlob => write!(f, "shares are not a whole blob"),
+            BlobInclusionError::MismatchedRows => write!(f, "rows do not match the shares"),
+            BlobInclusionError::InvalidShareProof { row } => {
+                write!(f, "shares are not included in row {}", row)
+            }
+            BlobInclusionError::InvalidRowProof { row } => {
+                write!(f, "row {} is not included in the data root", row)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlobInclusionError {}
+
+/// The bytes of a share after its namespace and info byte, less the sequence length and signer
+/// of a first share.
+fn share_capacity(first: bool, share_version: u8) -> usize {
+    let data = SHARE_SIZE - NAMESPACE_SIZE - 1;
+    match (first, share_version) {
+        (false, _) => data,
+        (true, 1) => data - 4 - 20,
+        (true, _) => data - 4,
+    }
+}
+
+/// The number of shares a sequence of `len` bytes spans.
+fn sequence_share_count(len: usize, share_version: u8) -> usize {
+    let rest = len.saturating_sub(share_capacity(true, share_version));
+    1 + rest.div_ceil(share_capacity(false, share_version))
+}
+
+/// Whether `shares` are a whole blob: a first share whose sequence length spans them all, then
+/// continuation shares.
+fn is_whole_blob(shares: &[Vec<u8>]) -> bool {
+    let info = |share: &[u8]| share[NAMESPACE_SIZE];
+    let first = &shares[0];
+    let (share_version, sequence_start) = (info(first) >> 1, info(first) & 1 == 1);
+    let len_bytes = first[NAMESPACE_SIZE + 1..NAMESPACE_SIZE + 5]
+        .try_into()
+        .expect("4 bytes");
+    let len = u32::from_be_bytes(len_bytes) as usize;
+    sequence_start
+        && shares[1..]
+            .iter()
+            .all(|share| info(share) == share_version << 1)
+        && sequence_share_count(len, share_version) == shares.len()
+}
+
+/// The smallest power of two at least `n`.
+fn round_up_power_of_two(n: usize) -> usize {
+    n.max(1).next_power_of_two()
+}
+
+/// The width of the subtrees whose roots a blob of `share_count` shares commits to.
+fn subtree_width(share_count: usize) -> usize {
+    let width = round_up_power_of_two(share_count.div_ceil(SUBTREE_ROOT_THRESHOLD));
+    // The blob's minimum square is the smallest power of two whose square fits the shares
+    let mut min_square_size = 1;
+    while min_square_size * min_square_size < share_count {
+        min_square_size *= 2;
+    }
+    width.min(min_square_size)
+}
+
+/// The share commitment of a blob: the Merkle root of the namespaced Merkle tree roots of its
+/// shares, split into a mountain range of subtrees of at most [`subtree_width`] shares.
+pub fn blob_commitment(namespace: &[u8], shares: &[Vec<u8>]) -> Option<[u8; 32]> {
+    let leaves: Vec<NamespacedHash> = shares
+        .iter()
+        .map(|share| namespaced_leaf(namespace, share))
+        .collect();
+    let max_width = subtree_width(shares.len());
+    let mut roots = vec![];
+    let mut rest = &leaves[..];
+    while !rest.is_empty() {
+        let width = if rest.len() >= max_width {
+            max_width
+        } else {
+            // The largest power of two that fits
+            1 << rest.len().ilog2()
+        };
+        roots.push(nmt_root(&rest[..width])?.to_vec());
+        rest = &rest[width..];
+    }
+    Some(merkle_root(&roots))
+}
+
+impl BlobInclusionProof {
+    /// Check that the shares are a whole blob of the namespace, included under `data_hash`, and
+    /// return its commitment.
+    pub fn verify(&self, data_hash: &[u8; 32]) -> Result<[u8; 32], BlobInclusionError> {
+        if self.namespace.len() != NAMESPACE_SIZE {
+            return Err(BlobInclusionError::MalformedNamespace);
+        }
+        if let Some(index) = self.shares.iter().position(|share| {
+            share.len() != SHARE_SIZE || share[..NAMESPACE_SIZE] != self.namespace[..]
+        }) {
+            return Err(BlobInclusionError::MalformedShare { index });
+        }
+        if self.shares.is_empty() || !is_whole_blob(&self.shares) {
+            return Err(BlobInclusionError::IncompleteBlob);
+        }
+
+        let first = &self
+            .rows
+            .first()
+            .ok_or(BlobInclusionError::MismatchedRows)?
+            .row_proof;
+        let (first_row, total) = (first.index, first.total);
+        // The data root commits to the row roots then the column roots of the extended square,
+        // whose first half of rows and of columns is the original data. Its width is a power of
+        // two: other totals take the same proof paths, and would shrink the rows.
+        if !total.is_power_of_two() || total < 4 {
+            return Err(BlobInclusionError::MismatchedRows);
+        }
+        let width = (total / 2) as usize;
+        let mut shares = &self.shares[..];
+        for (i, row) in self.rows.iter().enumerate() {
+            let index = row.row_proof.index;
+            let (start, end) = (row.share_proof.start as usize, row.share_proof.end as usize);
+            let original = row.row_proof.total == total && index < total / 4 && end <= width / 2;
+            // The shares must follow each other: every row but the first starts the row, and
+            // every row but the last ends the original data of the row
+            let contiguous =
+                (i == 0 || start == 0) && (i == self.rows.len() - 1 || end == width / 2);
+            if index != first_row + i as u64 || !original || !contiguous || start >= end {
+                return Err(BlobInclusionError::MismatchedRows);
+            }
+            if shares.len() < end - start {
+                return Err(BlobInclusionError::MismatchedRows);
+            }
+            let (row_shares, rest) = shares.split_at(end - start);
+            shares = rest;
+
+            let leaves: Vec<NamespacedHash> = row_shares
+                .iter()
+                .map(|share| namespaced_leaf(&self.namespace, share))
+                .collect();
+            if row
+                .share_proof
+                .root(width, &leaves)
+                .map(|root| root.to_vec())
+                != Some(row.row_root.clone())
+            {
+                return Err(BlobInclusionError::InvalidShareProof { row: index });
+            }
+            if !row.row_proof.verify(&row.row_root, data_hash) {
+                return Err(BlobInclusionError::InvalidRowProof { row: index });
+            }
+        }
+        if !shares.is_empty() {
+            return Err(BlobInclusionError::MismatchedRows);
+        }
+
+        blob_commitment(&self.namespace, &self.shares).ok_or(BlobInclusionError::IncompleteBlob)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dah::tests::prove;
+    use crate::nmt::tests::{namespace, prove_range};
+    use crate::{DataAvailabilityRoots, PARITY_NAMESPACE};
+
+    /// The shares of a blob of `len` bytes of `namespace`.
+    fn blob_shares(namespace: &[u8], len: usize) -> Vec<Vec<u8>> {
+        (0..sequence_share_count(len, 0))
+            .map(|i| {
+                let mut share = namespace.to_vec();
+                if i == 0 {
+                    share.push(1);
+                    share.extend((len as u32).to_be_bytes());
+                } else {
+                    share.push(0);
+                }
+                share.resize(SHARE_SIZE, i as u8 + 1);
+                share
+            })
+            .collect()
+    }
+
+    /// An extended square of `2 * size` rows that hold `data` in their first half, padded with
+    /// shares of a later namespace, and parity shares in their second half. The last `size` rows
+    /// are parity rows of a real square, which may hold data here to check that such rows are
+    /// refused.
+    struct Square {
+        data_hash: [u8; 32],
+        rows: Vec<Vec<NamespacedHash>>,
+        /// The row roots then the column roots.
+        roots: Vec<Vec<u8>>,
+    }
+
+    impl Square {
+        /// A 4x4 extended square.
+        fn new(data: Vec<Vec<u8>>) -> Self {
+            Self::sized(data, 2)
+        }
+
+        fn sized(mut data: Vec<Vec<u8>>, size: usize) -> Self {
+            assert!(data.len() <= 2 * size * size);
+            data.resize(2 * size * size, vec![0xff - 1; SHARE_SIZE]);
+            let rows: Vec<Vec<NamespacedHash>> = data
+                .chunks(size)
+                .enumerate()
+                .map(|(i, original)| {
+                    let mut row: Vec<NamespacedHash> = original
+                        .iter()
+                        .map(|share| namespaced_leaf(&share[..NAMESPACE_SIZE], share))
+                        .collect();
+                    row.extend((0..size).map(|_| namespaced_leaf(&PARITY_NAMESPACE, &[i as u8])));
+                    row
+                })
+                .collect();
+            let dah = DataAvailabilityRoots {
+                square_size: 2 * size as u64,
+                row_roots: rows
+                    .iter()
+                    .map(|row| nmt_root(row).unwrap().to_vec())
+                    .collect(),
+                column_roots: (0..2 * size as u8)
+                    .map(|i| vec![i; crate::NAMESPACED_HASH_SIZE])
+                    .collect(),
+            };
+            let roots = dah
+                .row_roots
+                .iter()
+                .chain(&dah.column_roots)
+                .cloned()
+                .collect();
+            Square {
+                data_hash: dah.hash(),
+                rows,
+                roots,
+            }
+        }
+
+        /// A proof of the shares `start..end` of `row`.
+        fn prove_row(&self, row: usize, start: usize, end: usize) -> RowInclusionProof {
+            RowInclusionProof {
+                row_root: self.roots[row].clone(),
+                row_proof: prove(&self.roots, row),
+                share_proof: prove_range(&self.rows[row], start, end),
+            }
+        }
+    }
+
+    /// A share of `namespace` that is not part of the blob under test.
+    fn other_share(namespace: &[u8]) -> Vec<u8> {
+        let mut share = namespace.to_vec();
+        share.resize(SHARE_SIZE, 0xaa);
+        share
+    }
+
+    /// A square whose original data is `padding` shares of another namespace followed by
+    /// `blob`, with proofs of the blob.
+    fn square(blob: &[Vec<u8>], padding: usize) -> ([u8; 32], Vec<RowInclusionProof>) {
+        let mut data = vec![other_share(&namespace(1)); padding];
+        data.extend_from_slice(blob);
+        assert!(data.len() <= 4);
+        let square = Square::new(data);
+
+        let mut proofs: Vec<RowInclusionProof> = vec![];
+        for index in padding..padding + blob.len() {
+            let (row, column) = (index / 2, index % 2);
+            match proofs.last() {
+                Some(last) if last.row_proof.index == row as u64 => {
+                    let start = last.share_proof.start as usize;
+                    *proofs.last_mut().unwrap() = square.prove_row(row, start, column + 1);
+                }
+                _ => proofs.push(square.prove_row(row, column, column + 1)),
+            }
+        }
+        (square.data_hash, proofs)
+    }
+
+    #[test]
+    fn test_blob_spanning_rows() {
+        let namespace = namespace(7);
+        // A first share and two continuation shares, from the second share of the first row
+        let shares = blob_shares(&namespace, 1000);
+        assert_eq!(shares.len(), 3);
+        let (data_hash, rows) = square(&shares, 1);
+        assert_eq!(rows.len(), 2);
+        let proof = BlobInclusionProof {
+            namespace: namespace.to_vec(),
+            shares,
+            rows,
+        };
+        assert_eq!(
+            proof.verify(&data_hash),
+            Ok(blob_commitment(&namespace, &proof.shares).unwrap())
+        );
+        assert_eq!(
+            proof.verify(&[0; 32]),
+            Err(BlobInclusionError::InvalidRowProof { row: 0 })
+        );
+
+        let mut missing = proof.clone();
+        missing.shares.pop();
+        assert_eq!(
+            missing.verify(&data_hash),
+            Err(BlobInclusionError::IncompleteBlob)
+        );
+
+        let mut tampered = proof.clone();
+        tampered.shares[2][100] ^= 1;
+        assert_eq!(
+            tampered.verify(&data_hash),
+            Err(BlobInclusionError::InvalidShareProof { row: 1 })
+        );
+    }
+
+    #[test]
+    fn test_rows_must_not_skip_shares() {
+        let namespace = namespace(7);
+        let shares = blob_shares(&namespace, 1000);
+        let (first, continuation) = (shares[0].clone(), &shares[1..]);
+        let other = other_share(&namespace);
+
+        // [first, other | c1, c2]: the first row ends before the end of its original data
+        let square = Square::new(vec![
+            first.clone(),
+            other.clone(),
+            continuation[0].clone(),
+            continuation[1].clone(),
+        ]);
+        let proof = BlobInclusionProof {
+            namespace: namespace.to_vec(),
+            shares: shares.clone(),
+            rows: vec![square.prove_row(0, 0, 1), square.prove_row(1, 0, 2)],
+        };
+        assert_eq!(
+            proof.verify(&square.data_hash),
+            Err(BlobInclusionError::MismatchedRows)
+        );
+
+        // [first, c1 | other, c2]: the second row does not start at its first share
+        let square = Square::new(vec![
+            first,
+            continuation[0].clone(),
+            other,
+            continuation[1].clone(),
+        ]);
+        let proof = BlobInclusionProof {
+            namespace: namespace.to_vec(),
+            shares,
+            rows: vec![square.prove_row(0, 0, 2), square.prove_row(1, 1, 2)],
+        };
+        assert_eq!(
+            proof.verify(&square.data_hash),
+            Err(BlobInclusionError::MismatchedRows)
+        );
+    }
+
+    #[test]
+    fn test_square_width_must_be_a_power_of_two() {
+        let namespace = namespace(7);
+        // A first share and a continuation share
+        let shares = blob_shares(&namespace, 600);
+        assert_eq!(shares.len(), 2);
+        let padding = other_share(&crate::nmt::tests::namespace(1));
+        let other = other_share(&namespace);
+
+        // [_, _, first, other | c1, ...] of an 8x8 square: the first row does not end the blob's
+        // part of it, but with 12 roots in place of 16 the rows look 3 shares wide
+        let square = Square::sized(
+            vec![
+                padding.clone(),
+                padding,
+                shares[0].clone(),
+                other,
+                shares[1].clone(),
+            ],
+            4,
+        );
+        let mut proof = BlobInclusionProof {
+            namespace: namespace.to_vec(),
+            shares,
+            rows: vec![square.prove_row(0, 2, 3), square.prove_row(1, 0, 1)],
+        };
+        assert_eq!(
+            proof.verify(&square.data_hash),
+            Err(BlobInclusionError::MismatchedRows)
+        );
+        for row in &mut proof.rows {
+            row.row_proof.total = 12;
+        }
+        assert_eq!(
+            proof.verify(&square.data_hash),
+            Err(BlobInclusionError::MismatchedRows)
+        );
+    }
+
+    #[test]
+    fn test_parity_rows_are_refused() {
+        let namespace = namespace(7);
+        let shares = blob_shares(&namespace, 1000);
+        // The blob in rows 2 and 3, which are parity rows of a 4x4 extended square
+        let mut data = vec![other_share(&crate::nmt::tests::namespace(1)); 4];
+        data.extend_from_slice(&shares);
+        let square = Square::new(data);
+        let proof = BlobInclusionProof {
+            namespace: namespace.to_vec(),
+            shares,
+            rows: vec![square.prove_row(2, 0, 2), square.prove_row(3, 0, 1)],
+        };
+        assert_eq!(
+            proof.verify(&square.data_hash),
+            Err(BlobInclusionError::MismatchedRows)
+        );
+    }
+
+    #[test]
+    fn test_commitment_subtrees() {
+        // Up to the threshold, each share is its own subtree
+        assert_eq!(subtree_width(3), 1);
+        assert_eq!(subtree_width(64), 1);
+        assert_eq!(subtree_width(65), 2);
+        assert_eq!(subtree_width(4096), 64);
+        // A blob never has subtrees wider than its minimum square
+        assert_eq!(subtree_width(2), 1);
+
+        let namespace = namespace(7);
+        let shares = blob_shares(&namespace, 100);
+        let leaf = namespaced_leaf(&namespace, &shares[0]).to_vec();
+        assert_eq!(
+            blob_commitment(&namespace, &shares),
+            Some(merkle_root(&[leaf]))
+        );
+    }
+}
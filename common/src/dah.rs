@@ -0,0 +1,206 @@
+use crate::nmt::split_point;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Size of a namespaced Merkle tree root: the minimum and maximum namespaces, then the hash.
+pub const NAMESPACED_HASH_SIZE: usize = 29 + 29 + 32;
+
+/// The row and column roots of a block's data availability header.
+///
+/// Light nodes sample shares against these roots, and the block header commits to them through
+/// its `data_hash`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataAvailabilityRoots {
+    /// Width of the extended data square, i.e. the number of row roots and of column roots.
+    pub square_size: u64,
+    /// Namespaced Merkle tree roots of the rows of the extended data square.
+    pub row_roots: Vec<Vec<u8>>,
+    /// Namespaced Merkle tree roots of the columns of the extended data square.
+    pub column_roots: Vec<Vec<u8>>,
+}
+
+fn hash_merkle_leaf(leaf: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0]);
+    hasher.update(leaf);
+    hasher.finalize().into()
+}
+
+fn hash_merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// RFC 6962 Merkle root of `leaves`, as computed by tendermint's `merkle.HashFromByteSlices`.
+pub fn merkle_root(leaves: &[Vec<u8>]) -> [u8; 32] {
+    match leaves.len() {
+        0 => Sha256::digest([]).into(),
+        1 => hash_merkle_leaf(&leaves[0]),
+        len => {
+            let split = split_point(len);
+            hash_merkle_node(
+                &merkle_root(&leaves[..split]),
+                &merkle_root(&leaves[split..]),
+            )
+        }
+    }
+}
+
+/// Proof that a leaf is included in an RFC 6962 Merkle tree, as tendermint's `merkle.Proof`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Position of the leaf, starting from 0.
+    pub index: u64,
+    /// Number of leaves in the tree.
+    pub total: u64,
+    /// Siblings from the leaf up to the root.
+    pub aunts: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// The root of the tree `leaf` is at `index` of, if the proof is well formed.
+    pub fn root(&self, leaf: &[u8]) -> Option<[u8; 32]> {
+        fn from_aunts(
+            index: u64,
+            total: u64,
+            leaf_hash: [u8; 32],
+            aunts: &[[u8; 32]],
+        ) -> Option<[u8; 32]> {
+            if index >= total {
+                return None;
+            }
+            if total == 1 {
+                // Every aunt must have been used on the way down
+                return aunts.is_empty().then_some(leaf_hash);
+            }
+            let (aunt, rest) = aunts.split_last()?;
+            let split = split_point(total as usize) as u64;
+            if index < split {
+                Some(hash_merkle_node(
+                    &from_aunts(index, split, leaf_hash, rest)?,
+                    aunt,
+                ))
+            } else {
+                Some(hash_merkle_node(
+                    aunt,
+                    &from_aunts(index - split, total - split, leaf_hash, rest)?,
+                ))
+            }
+        }
+        from_aunts(self.index, self.total, hash_merkle_leaf(leaf), &self.aunts)
+    }
+
+    /// Check that `leaf` is at `index` in the tree with the given `root`.
+    pub fn verify(&self, leaf: &[u8], root: &[u8; 32]) -> bool {
+        self.root(leaf).as_ref() == Some(root)
+    }
+}
+
+impl DataAvailabilityRoots {
+    /// Whether there are `square_size` roots of each kind, each of the size of a namespaced hash.
+    pub fn is_well_formed(&self) -> bool {
+        let square_size = self.square_size as usize;
+        square_size > 0
+            && self.row_roots.len() == square_size
+            && self.column_roots.len() == square_size
+            && self
+                .row_roots
+                .iter()
+                .chain(&self.column_roots)
+                .all(|root| root.len() == NAMESPACED_HASH_SIZE)
+    }
+
+    /// The hash committed to by the block header's `data_hash`: the Merkle root of the row
+    /// roots followed by the column roots.
+    pub fn hash(&self) -> [u8; 32] {
+        let roots: Vec<Vec<u8>> = self
+            .row_roots
+            .iter()
+            .chain(&self.column_roots)
+            .cloned()
+            .collect();
+        merkle_root(&roots)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_root_splits_at_powers_of_two() {
+        let leaves: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i]).collect();
+        let left = merkle_root(&leaves[..2]);
+        let right = merkle_root(&leaves[2..]);
+        let mut hasher = Sha256::new();
+        hasher.update([1]);
+        hasher.update(left);
+        hasher.update(right);
+        assert_eq!(merkle_root(&leaves), <[u8; 32]>::from(hasher.finalize()));
+        assert_ne!(merkle_root(&leaves[..1]), merkle_root(&[]));
+    }
+
+    /// A proof of `leaves[index]`.
+    pub(crate) fn prove(leaves: &[Vec<u8>], index: usize) -> MerkleProof {
+        fn aunts(leaves: &[Vec<u8>], index: usize) -> Vec<[u8; 32]> {
+            if leaves.len() == 1 {
+                return vec![];
+            }
+            let split = split_point(leaves.len());
+            if index < split {
+                let mut aunts = aunts(&leaves[..split], index);
+                aunts.push(merkle_root(&leaves[split..]));
+                aunts
+            } else {
+                let mut aunts = aunts(&leaves[split..], index - split);
+                aunts.push(merkle_root(&leaves[..split]));
+                aunts
+            }
+        }
+        MerkleProof {
+            index: index as u64,
+            total: leaves.len() as u64,
+            aunts: aunts(leaves, index),
+        }
+    }
+
+    #[test]
+    fn test_merkle_proofs() {
+        let leaves: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i]).collect();
+        let root = merkle_root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = prove(&leaves, index);
+            assert!(proof.verify(leaf, &root));
+            assert!(!proof.verify(&[9], &root));
+        }
+        let mut proof = prove(&leaves, 4);
+        proof.total = 4;
+        assert!(
+            !proof.verify(&leaves[4], &root),
+            "the index must be within the tree"
+        );
+
+        // A single leaf tree with the siblings of a larger one
+        let mut proof = prove(&leaves[..2], 0);
+        let root = merkle_root(&leaves[..2]);
+        assert!(proof.verify(&leaves[0], &root));
+        proof.total = 1;
+        assert_eq!(proof.root(&leaves[0]), None, "surplus aunts are refused");
+    }
+
+    #[test]
+    fn test_well_formed_roots() {
+        let root = vec![0; NAMESPACED_HASH_SIZE];
+        let mut roots = DataAvailabilityRoots {
+            square_size: 2,
+            row_roots: vec![root.clone(), root.clone()],
+            column_roots: vec![root.clone(), root],
+        };
+        assert!(roots.is_well_formed());
+        roots.column_roots.pop();
+        assert!(!roots.is_well_formed());
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// What a run of the sync program proves, written to stdin right after the verifying key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgramMode {
+    /// Verify a batch of headers starting from a trusted start header or from the head of a
+    /// previous proof.
+    Sync,
+    /// Merge a proof of A -> B and a proof of B -> C into a proof of A -> C.
+    Aggregate,
+}
@@ -0,0 +1,19 @@
+//! Types shared between the zkVM program and the host scripts.
+
+mod ancestry;
+mod blob;
+mod dah;
+mod input;
+mod mmr;
+mod nmt;
+mod public_values;
+mod rejection;
+
+pub use ancestry::*;
+pub use blob::*;
+pub use dah::*;
+pub use input::*;
+pub use mmr::*;
+pub use nmt::*;
+pub use public_values::*;
+pub use rejection::*;
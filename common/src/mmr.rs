@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain separation prefixes, so that leaves, inner nodes and the bagged root cannot collide.
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+const ROOT_PREFIX: u8 = 2;
+
+/// An append-only Merkle Mountain Range of header hashes.
+///
+/// Only the peaks are kept, which is enough to append new leaves and to compute the root, so the
+/// whole accumulator can be committed and carried from one recursion step to the next.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mmr {
+    /// Number of leaves appended so far.
+    pub leaf_count: u64,
+    /// Roots of the perfect subtrees, from the largest (leftmost) to the smallest.
+    pub peaks: Vec<[u8; 32]>,
+}
+
+pub(crate) fn hash_leaf(leaf: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(leaf);
+    hasher.finalize().into()
+}
+
+pub(crate) fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Bag the peaks from right to left and bind the leaf count into the root.
+pub(crate) fn bag_peaks(leaf_count: u64, peaks: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([ROOT_PREFIX]);
+    hasher.update(leaf_count.to_be_bytes());
+    let bagged = peaks
+        .iter()
+        .rev()
+        .copied()
+        .reduce(|acc, peak| hash_node(&peak, &acc))
+        .unwrap_or([0; 32]);
+    hasher.update(bagged);
+    hasher.finalize().into()
+}
+
+/// The leaf ranges of the perfect subtrees of an accumulator, as `(first leaf, height)` pairs
+/// from the largest to the smallest.
+fn subtrees(leaf_count: u64) -> Vec<(u64, u32)> {
+    let mut offset = 0;
+    (0..u64::BITS)
+        .rev()
+        .filter(|height| leaf_count >> height & 1 == 1)
+        .map(|height| {
+            let subtree = (offset, height);
+            offset += 1 << height;
+            subtree
+        })
+        .collect()
+}
+
+/// Root of the perfect subtree over `leaves`, whose length is a power of two.
+fn subtree_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = leaves.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_node(&pair[0], &pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+/// Proof that a leaf is included in an accumulator with a given root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MmrProof {
+    /// Position of the leaf, starting from 0.
+    pub leaf_index: u64,
+    /// Number of leaves in the accumulator the proof is against.
+    pub leaf_count: u64,
+    /// Siblings from the leaf up to the root of its subtree.
+    pub siblings: Vec<[u8; 32]>,
+    /// All peaks of the accumulator.
+    pub peaks: Vec<[u8; 32]>,
+}
+
+impl MmrProof {
+    /// Check that `leaf` is at `leaf_index` in the accumulator with the given `root`.
+    pub fn verify(&self, leaf: &[u8; 32], root: &[u8; 32]) -> bool {
+        let subtrees = subtrees(self.leaf_count);
+        let Some(position) = subtrees.iter().position(|(offset, height)| {
+            self.leaf_index >= *offset && self.leaf_index - offset < 1 << height
+        }) else {
+            return false;
+        };
+        let (offset, height) = subtrees[position];
+        if self.siblings.len() != height as usize || self.peaks.len() != subtrees.len() {
+            return false;
+        }
+
+        let local_index = self.leaf_index - offset;
+        let mut node = hash_leaf(leaf);
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            node = if local_index >> level & 1 == 1 {
+                hash_node(sibling, &node)
+            } else {
+                hash_node(&node, sibling)
+            };
+        }
+        node == self.peaks[position] && bag_peaks(self.leaf_count, &self.peaks) == *root
+    }
+}
+
+impl Mmr {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build an accumulator from all of its leaves.
+    pub fn from_leaves<'a>(leaves: impl IntoIterator<Item = &'a [u8; 32]>) -> Self {
+        let mut mmr = Self::new();
+        for leaf in leaves {
+            mmr.push(leaf);
+        }
+        mmr
+    }
+
+    /// Append a leaf, merging equal-sized peaks.
+    pub fn push(&mut self, leaf: &[u8; 32]) {
+        let mut node = hash_leaf(leaf);
+        let mut height = 0;
+        while self.leaf_count >> height & 1 == 1 {
+            let left = self.peaks.pop().expect("peaks do not match the leaf count");
+            node = hash_node(&left, &node);
+            height += 1;
+        }
+        self.peaks.push(node);
+        self.leaf_count += 1;
+    }
+
+    /// The root committing to every leaf and to the number of leaves.
+    pub fn root(&self) -> [u8; 32] {
+        bag_peaks(self.leaf_count, &self.peaks)
+    }
+
+    /// Prove the inclusion of `leaves[leaf_index]` in the accumulator of all `leaves`.
+    pub fn prove(leaves: &[[u8; 32]], leaf_index: u64) -> Option<MmrProof> {
+        let leaf_count = leaves.len() as u64;
+        if leaf_index >= leaf_count {
+            return None;
+        }
+        let subtrees = subtrees(leaf_count);
+        let (offset, height) = *subtrees
+            .iter()
+            .find(|(offset, height)| leaf_index >= *offset && leaf_index - offset < 1 << height)?;
+
+        // Walk up the subtree of the leaf, collecting the root of the sibling range at each level
+        let subtree = &leaves[offset as usize..(offset + (1 << height)) as usize];
+        let local_index = (leaf_index - offset) as usize;
+        let siblings = (0..height)
+            .map(|level| {
+                let sibling_start = ((local_index >> level) ^ 1) << level;
+                subtree_root(&subtree[sibling_start..sibling_start + (1 << level)])
+            })
+            .collect();
+
+        Some(MmrProof {
+            leaf_index,
+            leaf_count,
+            siblings,
+            peaks: Self::from_leaves(leaves).peaks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peaks_follow_leaf_count() {
+        let leaves: Vec<[u8; 32]> = (0..11u8).map(|i| [i; 32]).collect();
+        let mmr = Mmr::from_leaves(&leaves);
+        // 11 = 0b1011, one peak per set bit
+        assert_eq!(mmr.leaf_count, 11);
+        assert_eq!(mmr.peaks.len(), 3);
+        assert_eq!(
+            mmr.peaks[2],
+            hash_leaf(&leaves[10]),
+            "the smallest peak is the last leaf"
+        );
+        assert_ne!(mmr.root(), Mmr::from_leaves(&leaves[..10]).root());
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        let leaves: Vec<[u8; 32]> = (0..11u8).map(|i| [i; 32]).collect();
+        let root = Mmr::from_leaves(&leaves).root();
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = Mmr::prove(&leaves, index as u64).unwrap();
+            assert!(proof.verify(leaf, &root));
+            assert!(!proof.verify(&[0xff; 32], &root));
+        }
+        assert!(Mmr::prove(&leaves, 11).is_none());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::NAMESPACED_HASH_SIZE;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Size of a namespace: a version byte followed by a 28 byte ID.
+pub const NAMESPACE_SIZE: usize = 29;
+
+/// The namespace of the parity shares of the extended data square, which sorts after every other.
+pub const PARITY_NAMESPACE: [u8; NAMESPACE_SIZE] = [0xff; NAMESPACE_SIZE];
+
+/// A namespaced Merkle tree node: its minimum and maximum namespaces, then its hash.
+pub type NamespacedHash = [u8; NAMESPACED_HASH_SIZE];
+
+fn min_namespace(hash: &NamespacedHash) -> &[u8] {
+    &hash[..NAMESPACE_SIZE]
+}
+
+fn max_namespace(hash: &NamespacedHash) -> &[u8] {
+    &hash[NAMESPACE_SIZE..2 * NAMESPACE_SIZE]
+}
+
+/// The leaf of a share pushed to a row or column tree under `namespace`.
+pub fn namespaced_leaf(namespace: &[u8], share: &[u8]) -> NamespacedHash {
+    let mut hasher = Sha256::new();
+    hasher.update([0]);
+    hasher.update(namespace);
+    hasher.update(share);
+    let mut leaf = [0; NAMESPACED_HASH_SIZE];
+    leaf[..NAMESPACE_SIZE].copy_from_slice(namespace);
+    leaf[NAMESPACE_SIZE..2 * NAMESPACE_SIZE].copy_from_slice(namespace);
+    leaf[2 * NAMESPACE_SIZE..].copy_from_slice(&hasher.finalize());
+    leaf
+}
+
+/// The parent of two nodes, or `None` if they are not in namespace order.
+///
+/// As in Celestia's trees, the parity namespace is left out of the maximum namespace of a node
+/// that also covers data shares.
+pub fn namespaced_node(left: &NamespacedHash, right: &NamespacedHash) -> Option<NamespacedHash> {
+    if max_namespace(left) > min_namespace(right) {
+        return None;
+    }
+    let max = if min_namespace(left) == PARITY_NAMESPACE {
+        &PARITY_NAMESPACE[..]
+    } else if min_namespace(right) == PARITY_NAMESPACE {
+        max_namespace(left)
+    } else {
+        max_namespace(right)
+    };
+    let mut hasher = Sha256::new();
+    hasher.update([1]);
+    hasher.update(left);
+    hasher.update(right);
+    let mut node = [0; NAMESPACED_HASH_SIZE];
+    node[..NAMESPACE_SIZE].copy_from_slice(min_namespace(left));
+    node[NAMESPACE_SIZE..2 * NAMESPACE_SIZE].copy_from_slice(max);
+    node[2 * NAMESPACE_SIZE..].copy_from_slice(&hasher.finalize());
+    Some(node)
+}
+
+/// The largest power of two strictly smaller than `len`, where trees of `len` leaves split.
+pub(crate) fn split_point(len: usize) -> usize {
+    len.next_power_of_two() / 2
+}
+
+/// Root of the namespaced Merkle tree over `leaves`.
+pub fn nmt_root(leaves: &[NamespacedHash]) -> Option<NamespacedHash> {
+    match leaves.len() {
+        0 => None,
+        1 => Some(leaves[0]),
+        len => {
+            let split = split_point(len);
+            namespaced_node(&nmt_root(&leaves[..split])?, &nmt_root(&leaves[split..])?)
+        }
+    }
+}
+
+/// Proof that the leaves `start..end` of a namespaced Merkle tree are included in its root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NmtRangeProof {
+    pub start: u32,
+    /// End of the range, exclusive.
+    pub end: u32,
+    /// Roots of the largest subtrees left of and right of the range, from left to right.
+    pub nodes: Vec<Vec<u8>>,
+}
+
+impl NmtRangeProof {
+    /// The root of a tree of `width` leaves whose range is `leaves`, if the proof is well formed.
+    pub fn root(&self, width: usize, leaves: &[NamespacedHash]) -> Option<NamespacedHash> {
+        let (start, end) = (self.start as usize, self.end as usize);
+        if start >= end || end > width || leaves.len() != end - start {
+            return None;
+        }
+        let mut nodes = self.nodes.iter();
+        let root = self.subtree_root(0, width, leaves, &mut nodes)?;
+        // Every node must have been used
+        nodes.next().is_none().then_some(root)
+    }
+
+    fn subtree_root<'a>(
+        &self,
+        from: usize,
+        to: usize,
+        leaves: &[NamespacedHash],
+        nodes: &mut impl Iterator<Item = &'a Vec<u8>>,
+    ) -> Option<NamespacedHash> {
+        let (start, end) = (self.start as usize, self.end as usize);
+        if to <= start || from >= end {
+            return nodes.next()?.as_slice().try_into().ok();
+        }
+        if to - from == 1 {
+            return Some(leaves[from - start]);
+        }
+        let split = from + split_point(to - from);
+        let left = self.subtree_root(from, split, leaves, nodes)?;
+        let right = self.subtree_root(split, to, leaves, nodes)?;
+        namespaced_node(&left, &right)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A proof of the range `start..end` of `leaves`.
+    pub(crate) fn prove_range(
+        leaves: &[NamespacedHash],
+        start: usize,
+        end: usize,
+    ) -> NmtRangeProof {
+        fn collect(
+            leaves: &[NamespacedHash],
+            from: usize,
+            start: usize,
+            end: usize,
+            nodes: &mut Vec<Vec<u8>>,
+        ) {
+            let to = from + leaves.len();
+            if to <= start || from >= end {
+                nodes.push(nmt_root(leaves).unwrap().to_vec());
+            } else if leaves.len() > 1 {
+                let split = split_point(leaves.len());
+                collect(&leaves[..split], from, start, end, nodes);
+                collect(&leaves[split..], from + split, start, end, nodes);
+            }
+        }
+        let mut nodes = vec![];
+        collect(leaves, 0, start, end, &mut nodes);
+        NmtRangeProof {
+            start: start as u32,
+            end: end as u32,
+            nodes,
+        }
+    }
+
+    pub(crate) fn namespace(id: u8) -> [u8; NAMESPACE_SIZE] {
+        let mut namespace = [0; NAMESPACE_SIZE];
+        namespace[NAMESPACE_SIZE - 1] = id;
+        namespace
+    }
+
+    #[test]
+    fn test_parity_namespace_is_left_out() {
+        let data = namespaced_leaf(&namespace(1), b"data");
+        let parity = namespaced_leaf(&PARITY_NAMESPACE, b"parity");
+        let root = nmt_root(&[data, data, parity, parity]).unwrap();
+        assert_eq!(min_namespace(&root), namespace(1));
+        assert_eq!(max_namespace(&root), namespace(1));
+        assert!(
+            namespaced_node(&parity, &data).is_none(),
+            "nodes must be in namespace order"
+        );
+    }
+
+    #[test]
+    fn test_range_proofs() {
+        let leaves: Vec<NamespacedHash> = (0..8u8)
+            .map(|i| namespaced_leaf(&namespace(i / 2), &[i]))
+            .collect();
+        let root = nmt_root(&leaves).unwrap();
+        for (start, end) in [(0, 8), (0, 1), (3, 6), (7, 8)] {
+            let proof = prove_range(&leaves, start, end);
+            assert_eq!(proof.root(8, &leaves[start..end]), Some(root));
+            let mut tampered = leaves[start..end].to_vec();
+            tampered[0][NAMESPACED_HASH_SIZE - 1] ^= 1;
+            assert_ne!(proof.root(8, &tampered), Some(root));
+        }
+        let mut proof = prove_range(&leaves, 3, 6);
+        proof.nodes.push(root.to_vec());
+        assert_eq!(
+            proof.root(8, &leaves[3..6]),
+            None,
+            "extra nodes are refused"
+        );
+    }
+}
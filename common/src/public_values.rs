@@ -0,0 +1,306 @@
+use crate::{DataAvailabilityRoots, Mmr, Rejection};
+use core::{fmt, time::Duration};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Version of the [`SyncPublicValues`] layout.
+///
+/// Bump this whenever a field is added, removed or reordered, so that proofs committed with an
+/// older layout are refused instead of being decoded positionally into the wrong fields.
+pub const PUBLIC_VALUES_VERSION: u32 = 9;
+
+/// The values committed by every step of the sync program.
+///
+/// The guest commits this struct as a whole, the next recursion step decodes it from the
+/// previous proof's public values, and the host scripts decode it to inspect a proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncPublicValues {
+    /// Layout version, always the first field so it can be read on its own.
+    pub version: u32,
+    /// SHA-256 of the verifying key words of the program that produced the proof.
+    pub vkey_hash: [u8; 32],
+    /// The trusted header the chain of proofs starts from, either the genesis or a checkpoint.
+    pub start: Checkpoint,
+    /// The light client security policy every step was verified under.
+    pub policy: TrustPolicy,
+    /// The latest verified header.
+    pub head: VerifiedHead,
+    /// The time supplied by the host that the head was verified against.
+    pub verified_at: Timestamp,
+    /// Accumulator of the hashes of every verified header, from the start to the head.
+    pub history: Mmr,
+    /// The data availability header of the head, checked against its `data_hash`.
+    ///
+    /// Only committed by the program built with the `dah` feature, and `None` otherwise.
+    pub head_dah: Option<DataAvailabilityRoots>,
+    /// Why the step was rejected, or `None` if it verified successfully.
+    ///
+    /// The other fields of a rejected step are left at their defaults.
+    pub rejection: Option<Rejection>,
+}
+
+/// The light client parameters headers are verified with.
+///
+/// These mirror `tendermint_light_client_verifier::options::Options`, and must stay identical
+/// across every step of a chain of proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustPolicy {
+    /// How long a verified header can be trusted for.
+    pub trusting_period: Duration,
+    /// Numerator of the fraction of the trusted validators' power that must sign a new header.
+    pub trust_threshold_numerator: u64,
+    /// Denominator of the trust threshold fraction.
+    pub trust_threshold_denominator: u64,
+    /// Tolerated clock drift between the header time and the verification time.
+    pub clock_drift: Duration,
+}
+
+impl Default for TrustPolicy {
+    /// A two week trusting period, a 1/3 trust threshold and no clock drift.
+    fn default() -> Self {
+        Self {
+            trusting_period: Duration::from_secs(14 * 24 * 60 * 60),
+            trust_threshold_numerator: 1,
+            trust_threshold_denominator: 3,
+            clock_drift: Duration::default(),
+        }
+    }
+}
+
+/// Identifying fields of a verified header.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedHead {
+    /// Hash of the header.
+    pub hash: [u8; 32],
+    pub height: u64,
+    /// Header time.
+    pub time: Timestamp,
+    pub chain_id: String,
+    /// Root of the block's data, i.e. the Celestia data availability root.
+    pub data_hash: Option<[u8; 32]>,
+    /// Application state root after executing the previous block.
+    pub app_hash: Vec<u8>,
+    /// Hash of the validator set for the next block.
+    pub next_validators_hash: [u8; 32],
+}
+
+/// A trusted header a range of proofs starts from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Hash of the header.
+    pub hash: [u8; 32],
+    pub height: u64,
+    /// Header time.
+    pub time: Timestamp,
+}
+
+impl From<&VerifiedHead> for Checkpoint {
+    fn from(head: &VerifiedHead) -> Self {
+        Self {
+            hash: head.hash,
+            height: head.height,
+            time: head.time,
+        }
+    }
+}
+
+/// A point in time as seconds and nanoseconds since the Unix epoch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Timestamp {
+    pub seconds: i64,
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    pub fn from_unix_nanos(nanos: i128) -> Self {
+        Self {
+            seconds: nanos.div_euclid(1_000_000_000) as i64,
+            nanos: nanos.rem_euclid(1_000_000_000) as u32,
+        }
+    }
+
+    pub fn unix_nanos(&self) -> i128 {
+        self.seconds as i128 * 1_000_000_000 + self.nanos as i128
+    }
+
+    /// The current wall-clock time.
+    pub fn now() -> Self {
+        let since_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system time is before the Unix epoch");
+        Self::from_unix_nanos(since_epoch.as_nanos() as i128)
+    }
+}
+
+/// Reasons public values could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The values were committed with a different layout version.
+    UnsupportedVersion { found: u32, expected: u32 },
+    /// The bytes are not a valid encoding of the current layout.
+    Malformed(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion { found, expected } => write!(
+                f,
+                "unsupported public values version {} (expected {})",
+                found, expected
+            ),
+            DecodeError::Malformed(reason) => write!(f, "malformed public values: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decode public values whose first field is a `u32` layout version, checking it first.
+pub(crate) fn decode_versioned<T: Serialize + DeserializeOwned>(
+    bytes: &[u8],
+    expected: u32,
+) -> Result<T, DecodeError> {
+    let found: u32 =
+        bincode::deserialize(bytes).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    if found != expected {
+        return Err(DecodeError::UnsupportedVersion { found, expected });
+    }
+    let values: T =
+        bincode::deserialize(bytes).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    let size =
+        bincode::serialized_size(&values).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    if size as usize != bytes.len() {
+        return Err(DecodeError::Malformed(format!(
+            "expected {} bytes, got {}",
+            size,
+            bytes.len()
+        )));
+    }
+    Ok(values)
+}
+
+impl SyncPublicValues {
+    pub fn new(
+        vkey_hash: [u8; 32],
+        start: Checkpoint,
+        policy: TrustPolicy,
+        head: VerifiedHead,
+        verified_at: Timestamp,
+        history: Mmr,
+    ) -> Self {
+        Self {
+            version: PUBLIC_VALUES_VERSION,
+            vkey_hash,
+            start,
+            policy,
+            head,
+            verified_at,
+            history,
+            head_dah: None,
+            rejection: None,
+        }
+    }
+
+    /// The values committed for a step rejected for `rejection`.
+    pub fn rejected(vkey_hash: [u8; 32], rejection: Rejection) -> Self {
+        Self {
+            version: PUBLIC_VALUES_VERSION,
+            vkey_hash,
+            start: Checkpoint::default(),
+            policy: TrustPolicy::default(),
+            head: VerifiedHead::default(),
+            verified_at: Timestamp::default(),
+            history: Mmr::default(),
+            head_dah: None,
+            rejection: Some(rejection),
+        }
+    }
+
+    /// The same values, also committing the data availability header of the head.
+    pub fn with_head_dah(self, head_dah: Option<DataAvailabilityRoots>) -> Self {
+        Self { head_dah, ..self }
+    }
+
+    /// Whether the step verified successfully.
+    pub fn is_valid(&self) -> bool {
+        self.rejection.is_none()
+    }
+
+    /// Encode the values the same way `sp1_zkvm::io::commit` does.
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("failed to serialize public values")
+    }
+
+    /// Decode public values committed by the sync program, checking the layout version first.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        decode_versioned(bytes, PUBLIC_VALUES_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head() -> VerifiedHead {
+        VerifiedHead {
+            hash: [3; 32],
+            height: 75845,
+            time: Timestamp::from_unix_nanos(1_699_649_586_763_896_232),
+            chain_id: "celestia".to_string(),
+            data_hash: Some([4; 32]),
+            app_hash: vec![5; 32],
+            next_validators_hash: [6; 32],
+        }
+    }
+
+    fn values() -> SyncPublicValues {
+        SyncPublicValues::new(
+            [1; 32],
+            Checkpoint {
+                hash: [2; 32],
+                height: 1,
+                time: Timestamp::from_unix_nanos(1_698_777_600_000_000_000),
+            },
+            TrustPolicy::default(),
+            head(),
+            Timestamp::from_unix_nanos(1_699_649_600_000_000_000),
+            Mmr::from_leaves(&[[2; 32], [3; 32]]),
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let values = values();
+        let decoded = SyncPublicValues::decode(&values.encode()).unwrap();
+        assert_eq!(decoded, values);
+
+        let roots = DataAvailabilityRoots {
+            square_size: 1,
+            row_roots: vec![vec![7; 90]],
+            column_roots: vec![vec![8; 90]],
+        };
+        let values = values.with_head_dah(Some(roots));
+        let decoded = SyncPublicValues::decode(&values.encode()).unwrap();
+        assert_eq!(decoded, values);
+
+        let rejected = SyncPublicValues::rejected([1; 32], Rejection::ExpiredTrust { height: 7 });
+        let decoded = SyncPublicValues::decode(&rejected.encode()).unwrap();
+        assert_eq!(
+            decoded.rejection,
+            Some(Rejection::ExpiredTrust { height: 7 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_version() {
+        let mut values = values();
+        values.version = PUBLIC_VALUES_VERSION + 1;
+        assert_eq!(
+            SyncPublicValues::decode(&values.encode()),
+            Err(DecodeError::UnsupportedVersion {
+                found: PUBLIC_VALUES_VERSION + 1,
+                expected: PUBLIC_VALUES_VERSION
+            })
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Why the sync program rejected a step.
+///
+/// A rejected step is still committed, with the reason in place of a verified head, so that the
+/// host can report it from the public values of an execution or a proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rejection {
+    /// An input could not be decoded.
+    MalformedInput { input: String, reason: String },
+    /// The trust policy does not describe a valid trust threshold.
+    InvalidTrustThreshold,
+    /// A previous proof was produced by a different program.
+    VkeyMismatch,
+    /// The previous proof starts from a different trusted header.
+    StartMismatch,
+    /// The previous proof was verified under a different trust policy.
+    PolicyMismatch,
+    /// The trusted header is not the head of the previous proof, or two ranges do not meet.
+    LinkMismatch,
+    /// A previous proof committed a rejection.
+    PreviousResultFalse,
+    /// The verification time is before the one of the previous proof.
+    TimeWentBackwards,
+    /// No header to verify was given.
+    EmptyBatch,
+    /// The first header of a proof from a trusted start is not that start.
+    StartHashMismatch,
+    /// A header is not after the header it is verified from.
+    NonIncreasingHeight { height: u64 },
+    /// A header is not on the same chain as the header it is verified from.
+    ChainIdMismatch { height: u64 },
+    /// A header is after the verification time, beyond the clock drift.
+    HeaderFromFuture { height: u64 },
+    /// The trusted header is outside of the trusting period at the verification time.
+    ExpiredTrust { height: u64 },
+    /// Not enough of the trusted validators' voting power signed the header.
+    InsufficientVotingPower { height: u64 },
+    /// Not enough of the header's own validators' voting power signed it.
+    InsufficientSignedVotingPower { height: u64 },
+    /// A commit signature does not verify.
+    InvalidSignature { height: u64 },
+    /// The header failed another light client check.
+    InvalidHeader { height: u64, reason: String },
+    /// The leaves given for a merged range do not match its accumulator.
+    HistoryMismatch,
+    /// No data availability header was given for the head.
+    MissingDataAvailabilityHeader { height: u64 },
+    /// The data availability header of the head does not hash to its `data_hash`.
+    DataAvailabilityHeaderMismatch { height: u64 },
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::MalformedInput { input, reason } => {
+                write!(f, "could not decode {}: {}", input, reason)
+            }
+            Rejection::InvalidTrustThreshold => write!(f, "invalid trust threshold"),
+            Rejection::VkeyMismatch => write!(f, "previous proof is of a different program"),
+            Rejection::StartMismatch => write!(f, "previous proof has a different start"),
+            Rejection::PolicyMismatch => write!(f, "previous proof has a different trust policy"),
+            Rejection::LinkMismatch => write!(f, "proofs do not link up"),
+            Rejection::PreviousResultFalse => write!(f, "previous proof was rejected"),
+            Rejection::TimeWentBackwards => {
+                write!(f, "verification time is before the previous one")
+            }
+            Rejection::EmptyBatch => write!(f, "no headers to verify"),
+            Rejection::StartHashMismatch => write!(f, "first header is not the trusted start"),
+            Rejection::NonIncreasingHeight { height } => {
+                write!(f, "header {} does not increase the height", height)
+            }
+            Rejection::ChainIdMismatch { height } => {
+                write!(f, "header {} is on a different chain", height)
+            }
+            Rejection::HeaderFromFuture { height } => {
+                write!(f, "header {} is from the future", height)
+            }
+            Rejection::ExpiredTrust { height } => write!(
+                f,
+                "trusted header is outside of the trusting period when verifying header {}",
+                height
+            ),
+            Rejection::InsufficientVotingPower { height } => write!(
+                f,
+                "not enough trusted voting power signed header {}",
+                height
+            ),
+            Rejection::InsufficientSignedVotingPower { height } => {
+                write!(f, "not enough voting power signed header {}", height)
+            }
+            Rejection::InvalidSignature { height } => {
+                write!(f, "invalid signature on header {}", height)
+            }
+            Rejection::InvalidHeader { height, reason } => {
+                write!(f, "header {} is invalid: {}", height, reason)
+            }
+            Rejection::HistoryMismatch => write!(f, "leaves do not match the accumulator"),
+            Rejection::MissingDataAvailabilityHeader { height } => {
+                write!(f, "no data availability header for header {}", height)
+            }
+            Rejection::DataAvailabilityHeaderMismatch { height } => write!(
+                f,
+                "data availability header does not match the data hash of header {}",
+                height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
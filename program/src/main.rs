@@ -3,98 +3,279 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 use sha2::{Sha256, Digest};
-mod buffer;
-use buffer::Buffer;
-use core::time::Duration;
+use cryptographic_sync_common::{
+    Checkpoint, DataAvailabilityRoots, Mmr, ProgramMode, Rejection, SyncPublicValues, Timestamp, TrustPolicy,
+    VerifiedHead,
+};
+use serde::de::DeserializeOwned;
 use tendermint_light_client_verifier::{
-    options::Options, types::LightBlock, ProdVerifier, Verdict, Verifier,
+    errors::VerificationErrorDetail, options::Options, types::{Hash, LightBlock, Time, TrustThreshold},
+    ProdVerifier, Verdict, Verifier,
 };
 
-pub fn main() {
-    // NOTE: values of n larger than 186 will overflow the u128 type,
-    // resulting in output that doesn't match fibonacci sequence.
-    // However, the resulting proof will still be valid!
+/// A SHA-256 tendermint hash as a fixed-size array.
+fn hash_bytes(hash: &Hash) -> [u8; 32] {
+    hash.as_bytes().try_into().expect("hash is not 32 bytes")
+}
 
-    let vkey: [u32; 8] = sp1_zkvm::io::read();
-    let byte_slice: &[u8] = unsafe {
-        core::slice::from_raw_parts(vkey.as_ptr() as *const u8, vkey.len() * core::mem::size_of::<u32>())
-    };
-    let hash_of_vkey = Sha256::digest(byte_slice);
-    sp1_zkvm::io::commit(&hash_of_vkey.to_vec());
+/// Hash of a light block's header as a fixed-size array.
+fn header_hash(block: &LightBlock) -> [u8; 32] {
+    hash_bytes(&block.signed_header.header().hash())
+}
+
+/// The fields of a light block's header that are committed for the latest verified header.
+fn verified_head(block: &LightBlock) -> VerifiedHead {
+    let header = block.signed_header.header();
+    VerifiedHead {
+        hash: header_hash(block),
+        height: header.height.value(),
+        time: Timestamp::from_unix_nanos(header.time.unix_timestamp_nanos()),
+        chain_id: header.chain_id.to_string(),
+        data_hash: header.data_hash.as_ref().map(hash_bytes),
+        app_hash: header.app_hash.as_bytes().to_vec(),
+        next_validators_hash: hash_bytes(&header.next_validators_hash),
+    }
+}
+
+/// The light client options for a trust policy.
+fn options(policy: &TrustPolicy) -> Result<Options, Rejection> {
+    Ok(Options {
+        trust_threshold: TrustThreshold::new(
+            policy.trust_threshold_numerator,
+            policy.trust_threshold_denominator,
+        )
+        .map_err(|_| Rejection::InvalidTrustThreshold)?,
+        trusting_period: policy.trusting_period,
+        clock_drift: policy.clock_drift,
+    })
+}
+
+/// Decode a CBOR encoded input, naming it in the rejection if it is malformed.
+fn decode_cbor<T: DeserializeOwned>(bytes: &[u8], input: &str) -> Result<T, Rejection> {
+    serde_cbor::from_slice(bytes).map_err(|e| Rejection::MalformedInput {
+        input: input.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Decode the public values of a previous proof, which must not have been rejected.
+fn decode_previous(public_values: &[u8], input: &str) -> Result<SyncPublicValues, Rejection> {
+    let previous = SyncPublicValues::decode(public_values).map_err(|e| Rejection::MalformedInput {
+        input: input.to_string(),
+        reason: e.to_string(),
+    })?;
+    if !previous.is_valid() {
+        return Err(Rejection::PreviousResultFalse);
+    }
+    Ok(previous)
+}
 
+/// Read the data availability header of `head`, which must hash to the head's data hash.
+#[cfg(feature = "dah")]
+fn read_head_dah(head: &LightBlock) -> Result<DataAvailabilityRoots, Rejection> {
+    let height = head.height().value();
+    let dah: Option<DataAvailabilityRoots> = sp1_zkvm::io::read();
+    let dah = dah.ok_or(Rejection::MissingDataAvailabilityHeader { height })?;
+    let data_hash = head.signed_header.header().data_hash.as_ref().map(hash_bytes);
+    if !dah.is_well_formed() || data_hash != Some(dah.hash()) {
+        return Err(Rejection::DataAvailabilityHeaderMismatch { height });
+    }
+    Ok(dah)
+}
+
+/// Verify that `untrusted` can be trusted given `trusted`, using skipping verification.
+fn verify_transition(trusted: &LightBlock, untrusted: &LightBlock, opt: &Options, now: Time) -> Result<(), Rejection> {
+    let height = untrusted.height().value();
+    // Ensure that we only move forward, and on the same chain
+    if untrusted.height() <= trusted.height() {
+        return Err(Rejection::NonIncreasingHeight { height });
+    }
+    if untrusted.signed_header.header().chain_id != trusted.signed_header.header().chain_id {
+        return Err(Rejection::ChainIdMismatch { height });
+    }
+
+    // Perform Tendermint (Celestia consensus) verification
+    let vp = ProdVerifier::default();
+    let verdict = vp.verify_update_header(
+        untrusted.as_untrusted_state(),
+        trusted.as_trusted_state(),
+        opt,
+        now,
+    );
+    match verdict {
+        Verdict::Success => Ok(()),
+        Verdict::NotEnoughTrust(_) => Err(Rejection::InsufficientVotingPower { height }),
+        Verdict::Invalid(detail) => Err(match detail {
+            VerificationErrorDetail::NotWithinTrustPeriod(_) => Rejection::ExpiredTrust { height },
+            VerificationErrorDetail::HeaderFromTheFuture(_) => Rejection::HeaderFromFuture { height },
+            VerificationErrorDetail::InsufficientSignersOverlap(_) => {
+                Rejection::InsufficientSignedVotingPower { height }
+            },
+            VerificationErrorDetail::InvalidSignature(_) => Rejection::InvalidSignature { height },
+            detail => Rejection::InvalidHeader { height, reason: detail.to_string() },
+        }),
+    }
+}
+
+/// Verify a batch of headers, either from a trusted start header (the genesis or any checkpoint)
+/// or from the head of a previous proof.
+fn sync(vkey: &[u32; 8], hash_of_vkey: [u8; 32]) -> Result<SyncPublicValues, Rejection> {
     let public_values: Vec<u8> = sp1_zkvm::io::read();
-    let mut public_values_buffer = Buffer::from(&public_values);
     let public_values_digest = Sha256::digest(&public_values);
 
-    let genesis_hash = sp1_zkvm::io::read_vec();
-    sp1_zkvm::io::commit(&genesis_hash);
+    let start_hash: Vec<u8> = sp1_zkvm::io::read_vec();
+    let start_hash: [u8; 32] = start_hash.try_into().map_err(|_| Rejection::MalformedInput {
+        input: "start hash".to_string(),
+        reason: "not 32 bytes".to_string(),
+    })?;
+    let policy: TrustPolicy = sp1_zkvm::io::read();
+    let opt = options(&policy)?;
+    let now: Timestamp = sp1_zkvm::io::read();
+    let now_time = Time::from_unix_timestamp(now.seconds, now.nanos).map_err(|e| Rejection::MalformedInput {
+        input: "verification time".to_string(),
+        reason: e.to_string(),
+    })?;
 
+    // The trusted header, and the batch of headers to verify in order starting from it
     let h1_bytes = sp1_zkvm::io::read_vec();
-    let h2_bytes = sp1_zkvm::io::read_vec();
-    let h1: Option<LightBlock> = serde_cbor::from_slice(&h1_bytes).expect("couldn't deserialize h1");
-    let h2: LightBlock = serde_cbor::from_slice(&h2_bytes).expect("couldn't deserialize h2");
-    // commit h2 hash
-    sp1_zkvm::io::commit(&h2.signed_header.header().hash().as_bytes().to_vec());
+    let headers_bytes = sp1_zkvm::io::read_vec();
+    let h1: Option<LightBlock> = decode_cbor(&h1_bytes, "h1")?;
+    let headers: Vec<LightBlock> = decode_cbor(&headers_bytes, "headers")?;
+    let h2 = headers.last().ok_or(Rejection::EmptyBatch)?;
+    let head = verified_head(h2);
+    // The host always writes the head's data availability header, which only this feature reads
+    #[cfg(feature = "dah")]
+    let head_dah = Some(read_head_dah(h2)?);
+    #[cfg(not(feature = "dah"))]
+    let head_dah: Option<DataAvailabilityRoots> = None;
 
-    match h1 {
-        Some(h1) => {
+    // Ensure that no header is from the future, allowing for clock drift
+    let latest_allowed_time = (now_time + policy.clock_drift).map_err(|e| Rejection::MalformedInput {
+        input: "verification time".to_string(),
+        reason: e.to_string(),
+    })?;
+    if let Some(header) = headers.iter().find(|header| header.time() > latest_allowed_time) {
+        return Err(Rejection::HeaderFromFuture { height: header.height().value() });
+    }
 
+    let (start, trusted, to_verify, mut history) = match h1 {
+        Some(h1) => {
+            let previous = decode_previous(&public_values, "previous public values")?;
             // Ensure that we are verifying a proof of the same circuit as ourself
-            let last_vkey_hash: Vec<u8> = public_values_buffer.read();
-            if last_vkey_hash != hash_of_vkey.to_vec() {
-                panic!("not valid!");
+            if previous.vkey_hash != hash_of_vkey {
+                return Err(Rejection::VkeyMismatch);
+            }
+            // Ensure that the previous proof has the same start as the current proof
+            if previous.start.hash != start_hash {
+                return Err(Rejection::StartMismatch);
             }
-            // Ensure that the previous proof has the same genesis hash as the current proof
-            let last_genesis_hash: Vec<u8> = public_values_buffer.read();
-            if last_genesis_hash != genesis_hash {
-                panic!("not valid!");
+            // Ensure that the previous proof was verified under the same security policy
+            if previous.policy != policy {
+                return Err(Rejection::PolicyMismatch);
             }
             // Ensure that previous proof has the h2 hash as the current h1 hash
-            let last_h2_hash: Vec<u8> = public_values_buffer.read();
-            if last_h2_hash != h1.signed_header.header().hash().as_bytes() {
-                panic!("not valid!");
+            if previous.head.hash != header_hash(&h1) {
+                return Err(Rejection::LinkMismatch);
             }
-            // Ensure that previous proof is valid
-            let last_result: bool = public_values_buffer.read();
-            if !last_result {
-                panic!("not valid!");
+            // Ensure that time does not go backwards between steps
+            if now < previous.verified_at {
+                return Err(Rejection::TimeWentBackwards);
             }
 
-            // Verify the previous recursion layer
-            sp1_zkvm::lib::verify::verify_sp1_proof(&vkey, &public_values_digest.into());
-
-            // Perform Tendermint (Celestia consensus) verification
-            let vp = ProdVerifier::default();
-            let opt = Options {
-                trust_threshold: Default::default(),
-                // 2 week trusting period.
-                trusting_period: Duration::from_secs(14 * 24 * 60 * 60),
-                clock_drift: Default::default(),
-            };
-            let verify_time = h2.time() + Duration::from_secs(20);
-            let verdict = vp.verify_update_header(
-                h2.as_untrusted_state(),
-                h1.as_trusted_state(),
-                &opt,
-                verify_time.unwrap(),
-            );
-            match verdict {
-                Verdict::Success => {
-                    sp1_zkvm::io::commit(&true);
-                },
-                _ => {
-                    panic!("verification failed");
-                }
-            }
+            // Verify the previous recursion layer, once for the whole batch
+            sp1_zkvm::lib::verify::verify_sp1_proof(vkey, &public_values_digest.into());
 
+            (previous.start, h1, &headers[..], previous.history)
         },
         None => {
-            if h2.signed_header.header().hash().as_bytes() == genesis_hash {
-                sp1_zkvm::io::commit(&true);
-            } else {
-                panic!("expected h2 == genesis hash");
+            // The first header is the trusted start itself, the rest are verified from it
+            if header_hash(&headers[0]) != start_hash {
+                return Err(Rejection::StartHashMismatch);
             }
+            let history = Mmr::from_leaves([&start_hash]);
+            (Checkpoint::from(&verified_head(&headers[0])), headers[0].clone(), &headers[1..], history)
         }
+    };
+
+    // Walk the batch, each verified header becoming the trusted header for the next one
+    let mut trusted = &trusted;
+    for header in to_verify {
+        // Report the cycles of each transition to the executor, keyed by the untrusted height
+        println!("cycle-tracker-report-start: header {}", header.height());
+        verify_transition(trusted, header, &opt, now_time)?;
+        println!("cycle-tracker-report-end: header {}", header.height());
+        history.push(&header_hash(header));
+        trusted = header;
+    }
+
+    Ok(SyncPublicValues::new(hash_of_vkey, start, policy, head, now, history).with_head_dah(head_dah))
+}
+
+/// Merge a proof of A -> B and a proof of B -> C into a proof of A -> C.
+fn aggregate(vkey: &[u32; 8], hash_of_vkey: [u8; 32]) -> Result<SyncPublicValues, Rejection> {
+    let left_public_values: Vec<u8> = sp1_zkvm::io::read();
+    let right_public_values: Vec<u8> = sp1_zkvm::io::read();
+    // Every header hash accumulated by the right range, starting with its start
+    let right_leaves: Vec<[u8; 32]> = sp1_zkvm::io::read();
+    let left = decode_previous(&left_public_values, "left public values")?;
+    let right = decode_previous(&right_public_values, "right public values")?;
+
+    // Ensure that both proofs are of the same circuit as ourself
+    if left.vkey_hash != hash_of_vkey || right.vkey_hash != hash_of_vkey {
+        return Err(Rejection::VkeyMismatch);
+    }
+    // Ensure that the right range starts where the left range ends
+    if right.start.hash != left.head.hash || right.start.height != left.head.height {
+        return Err(Rejection::LinkMismatch);
+    }
+    // Ensure that both ranges were verified under the same security policy, on the same chain
+    if left.policy != right.policy {
+        return Err(Rejection::PolicyMismatch);
+    }
+    if right.head.height <= left.head.height {
+        return Err(Rejection::NonIncreasingHeight { height: right.head.height });
+    }
+    if right.head.chain_id != left.head.chain_id {
+        return Err(Rejection::ChainIdMismatch { height: right.head.height });
+    }
+    // Ensure that time does not go backwards from the left range to the right one
+    if right.verified_at < left.verified_at {
+        return Err(Rejection::TimeWentBackwards);
+    }
+
+    // Ensure that the right leaves are exactly the ones the right range accumulated
+    if Mmr::from_leaves(&right_leaves) != right.history || right_leaves[0] != right.start.hash {
+        return Err(Rejection::HistoryMismatch);
+    }
+    // The right start is already the last leaf of the left history
+    let mut history = left.history;
+    for leaf in &right_leaves[1..] {
+        history.push(leaf);
     }
 
-}
\ No newline at end of file
+    // Verify both ranges, in the order their proofs were written
+    sp1_zkvm::lib::verify::verify_sp1_proof(vkey, &Sha256::digest(&left_public_values).into());
+    sp1_zkvm::lib::verify::verify_sp1_proof(vkey, &Sha256::digest(&right_public_values).into());
+
+    // The head was verified against the right range's verification time
+    Ok(SyncPublicValues::new(hash_of_vkey, left.start, left.policy, right.head, right.verified_at, history)
+        .with_head_dah(right.head_dah))
+}
+
+pub fn main() {
+    let vkey: [u32; 8] = sp1_zkvm::io::read();
+    let byte_slice: &[u8] = unsafe {
+        core::slice::from_raw_parts(vkey.as_ptr() as *const u8, vkey.len() * core::mem::size_of::<u32>())
+    };
+    let hash_of_vkey: [u8; 32] = Sha256::digest(byte_slice).into();
+
+    let mode: ProgramMode = sp1_zkvm::io::read();
+    let result = match mode {
+        ProgramMode::Sync => sync(&vkey, hash_of_vkey),
+        ProgramMode::Aggregate => aggregate(&vkey, hash_of_vkey),
+    };
+
+    // A rejected step is committed with its reason, so the host can report it
+    let public_values = result.unwrap_or_else(|rejection| SyncPublicValues::rejected(hash_of_vkey, rejection));
+    sp1_zkvm::io::commit(&public_values);
+}
@@ -1,5 +1,12 @@
-use sp1_helper::build_program;
+use sp1_helper::{build_program, build_program_with_args, BuildArgs};
 
 fn main() {
-    build_program("../program")
+    // Cargo sets this for the script's own `dah` feature, which is forwarded to the sync program
+    if std::env::var_os("CARGO_FEATURE_DAH").is_some() {
+        build_program_with_args("../program", BuildArgs { features: vec!["dah".to_string()], ..Default::default() });
+    } else {
+        build_program("../program");
+    }
+    build_program("../ancestry-program");
+    build_program("../blob-program");
 }
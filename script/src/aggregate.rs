@@ -0,0 +1,165 @@
+//! Proving header segments independently and merging them pairwise into a single range proof.
+use crate::convert::Dahs;
+use anyhow::{anyhow, bail, Result};
+use cryptographic_sync_common::{DataAvailabilityRoots, ProgramMode, Timestamp, TrustPolicy};
+use sp1_sdk::{HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey};
+use std::{num::NonZeroUsize, thread};
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// The accumulator leaves of a run of headers: their hashes, in order.
+pub fn leaves(headers: &[LightBlock]) -> Vec<[u8; 32]> {
+    headers
+        .iter()
+        .map(|header| header.signed_header.header().hash().as_bytes().try_into().expect("header hash is not 32 bytes"))
+        .collect()
+}
+
+/// Split `headers` into segments of at most `segment_size` transitions each.
+///
+/// Neighbouring segments share their boundary header, so that the proof of one segment ends
+/// where the proof of the next one starts.
+pub fn segments(headers: &[LightBlock], segment_size: usize) -> Vec<Vec<LightBlock>> {
+    assert!(segment_size > 0, "segment size must be positive");
+    if headers.len() <= 1 {
+        return vec![headers.to_vec()];
+    }
+    (0..headers.len() - 1)
+        .step_by(segment_size)
+        .map(|start| headers[start..=(start + segment_size).min(headers.len() - 1)].to_vec())
+        .collect()
+}
+
+/// Prove that every header of `segment` verifies from its first header.
+///
+/// The first header is committed as the trusted start checkpoint of the proof, so on its own the
+/// proof only attests `segment[0] -> segment[last]`; it is anchored by merging it after a range
+/// ending at `segment[0]`, or by the consumer already trusting that checkpoint.
+pub fn prove_segment(
+    client: &ProverClient,
+    pk: &SP1ProvingKey,
+    vk: &SP1VerifyingKey,
+    policy: &TrustPolicy,
+    segment: &[LightBlock],
+    dahs: &Dahs,
+    now: &Timestamp,
+) -> Result<SP1ProofWithPublicValues> {
+    let head = segment.last().ok_or_else(|| anyhow!("empty segment"))?.height().value();
+    let stdin = segment_stdin(vk, policy, segment, dahs.get(&head), now)?;
+    client.prove(pk, stdin).compressed().run().map_err(|e| anyhow!("could not prove segment to {}: {}", head, e))
+}
+
+/// The program input verifying every header of `segment` from its first header at `now`, with
+/// the data availability header of its last header if known.
+pub fn segment_stdin(
+    vk: &SP1VerifyingKey,
+    policy: &TrustPolicy,
+    segment: &[LightBlock],
+    head_dah: Option<&DataAvailabilityRoots>,
+    now: &Timestamp,
+) -> Result<SP1Stdin> {
+    let first = segment.first().ok_or_else(|| anyhow!("empty segment"))?;
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&vk.hash_u32());
+    stdin.write(&ProgramMode::Sync);
+    // No previous proof and no trusted header besides the segment's own first one
+    stdin.write(&Vec::<u8>::new());
+    stdin.write_vec(first.signed_header.header().hash().as_bytes().to_vec());
+    stdin.write(policy);
+    stdin.write(now);
+    stdin.write_vec(serde_cbor::to_vec(&None::<LightBlock>)?);
+    stdin.write_vec(serde_cbor::to_vec(segment)?);
+    stdin.write(&head_dah);
+    Ok(stdin)
+}
+
+/// Merge a proof of A -> B and a proof of B -> C into a proof of A -> C.
+///
+/// `right_leaves` are the header hashes accumulated by the right proof, from B to C, which the
+/// program appends to the left proof's accumulator.
+pub fn prove_merge(
+    client: &ProverClient,
+    pk: &SP1ProvingKey,
+    vk: &SP1VerifyingKey,
+    left: &SP1ProofWithPublicValues,
+    right: &SP1ProofWithPublicValues,
+    right_leaves: &[[u8; 32]],
+) -> Result<SP1ProofWithPublicValues> {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&vk.hash_u32());
+    stdin.write(&ProgramMode::Aggregate);
+    stdin.write(&left.public_values.to_vec());
+    stdin.write(&right.public_values.to_vec());
+    stdin.write(&right_leaves.to_vec());
+    for proof in [left, right] {
+        let inner = *match proof.proof.clone() {
+            SP1Proof::Compressed(c) => c,
+            _ => bail!("merged proof is not a compressed proof"),
+        };
+        stdin.write_proof(inner, vk.vk.clone());
+    }
+    client.prove(pk, stdin).compressed().run().map_err(|e| anyhow!("could not prove merge: {}", e))
+}
+
+/// Run `prove` on every item, at most `jobs` at a time, failing with the first error.
+fn prove_concurrently<T: Sync, R: Send>(
+    items: &[T],
+    jobs: NonZeroUsize,
+    prove: impl Fn(&T) -> Result<R> + Sync,
+) -> Result<Vec<R>> {
+    let mut proved = Vec::with_capacity(items.len());
+    for chunk in items.chunks(jobs.get()) {
+        let results: Vec<Result<R>> = thread::scope(|s| {
+            let handles: Vec<_> = chunk.iter().map(|item| s.spawn(|| prove(item))).collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(anyhow!("prover thread panicked"))))
+                .collect()
+        });
+        for result in results {
+            proved.push(result?);
+        }
+    }
+    Ok(proved)
+}
+
+/// Prove `headers[0] -> headers[last]` by proving segments and merging them level by level.
+///
+/// The proofs of each level are independent of each other and are proved up to `jobs` at a time,
+/// so with enough jobs the wall-clock time grows with the depth of the tree rather than with the
+/// number of segments. Every segment is verified at the same time, as merges refuse a right range
+/// verified before its left range.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_tree(
+    client: &ProverClient,
+    pk: &SP1ProvingKey,
+    vk: &SP1VerifyingKey,
+    policy: &TrustPolicy,
+    headers: &[LightBlock],
+    dahs: &Dahs,
+    segment_size: usize,
+    jobs: NonZeroUsize,
+) -> Result<SP1ProofWithPublicValues> {
+    let segments = segments(headers, segment_size);
+    println!("proving {} segments", segments.len());
+    let now = Timestamp::now();
+    // Each proof is kept along with the leaves it accumulated, which its parent merge needs
+    let mut level: Vec<(SP1ProofWithPublicValues, Vec<[u8; 32]>)> = prove_concurrently(&segments, jobs, |segment| {
+        Ok((prove_segment(client, pk, vk, policy, segment, dahs, &now)?, leaves(segment)))
+    })?;
+
+    while level.len() > 1 {
+        println!("merging {} proofs", level.len());
+        let pairs: Vec<_> = level.chunks(2).collect();
+        level = prove_concurrently(&pairs, jobs, |pair| match pair {
+            [(left, left_leaves), (right, right_leaves)] => {
+                let merged = prove_merge(client, pk, vk, left, right, right_leaves)?;
+                let mut merged_leaves = left_leaves.clone();
+                merged_leaves.extend_from_slice(&right_leaves[1..]);
+                Ok((merged, merged_leaves))
+            }
+            [single] => Ok(single.clone()),
+            _ => unreachable!(),
+        })?;
+    }
+    level.pop().map(|(proof, _)| proof).ok_or_else(|| anyhow!("no proof"))
+}
@@ -0,0 +1,65 @@
+//! Proving that an older header is an ancestor of the head of a sync proof.
+use crate::{source::HeaderSource, store::HeaderStore};
+use anyhow::{anyhow, bail, ensure, Result};
+use cryptographic_sync_common::AncestryPublicValues;
+use sp1_sdk::{HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
+use tendermint::block::Header;
+
+pub const ANCESTRY_ELF: &[u8] = include_bytes!("../../ancestry-program/elf/riscv32im-succinct-zkvm-elf");
+
+/// Every header from `head_height` back to `ancestor_height`, newest first.
+///
+/// Headers already in the store are taken from there, the others are fetched from their commits.
+pub async fn fetch_ancestry(
+    store: &HeaderStore,
+    client: Option<&impl HeaderSource>,
+    head_height: u64,
+    ancestor_height: u64,
+) -> Result<Vec<Header>> {
+    ensure!(
+        ancestor_height <= head_height,
+        "height {} is after the head at {}",
+        ancestor_height,
+        head_height
+    );
+    let mut headers = vec![];
+    for height in (ancestor_height..=head_height).rev() {
+        if let Some(block) = store.get(height)? {
+            headers.push(block.signed_header.header().clone());
+            continue;
+        }
+        let client = client.ok_or_else(|| anyhow!("header {} is not cached and no RPC endpoint is set", height))?;
+        headers.push(client.fetch_header(height).await?);
+    }
+    Ok(headers)
+}
+
+/// Prove that the last of `headers` is an ancestor of the head of `sync_proof`.
+///
+/// `headers` must start at the sync head and go back one block at a time.
+pub fn prove_ancestry(
+    client: &ProverClient,
+    sync_vk: &SP1VerifyingKey,
+    sync_proof: &SP1ProofWithPublicValues,
+    headers: &[Header],
+) -> Result<SP1ProofWithPublicValues> {
+    let (pk, _) = client.setup(ANCESTRY_ELF);
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&sync_vk.hash_u32());
+    stdin.write(&sync_proof.public_values.to_vec());
+    stdin.write_vec(serde_cbor::to_vec(headers)?);
+    let sync_proof_inner = *match sync_proof.proof.clone() {
+        SP1Proof::Compressed(c) => c,
+        _ => bail!("sync proof is not a compressed proof"),
+    };
+    stdin.write_proof(sync_proof_inner, sync_vk.vk.clone());
+
+    let proof = client.prove(&pk, stdin).compressed().run()?;
+    let public_values = AncestryPublicValues::decode(&proof.public_values.to_vec())?;
+    println!(
+        "proved height {} is an ancestor of height {}",
+        public_values.ancestor.height,
+        headers[0].height
+    );
+    Ok(proof)
+}
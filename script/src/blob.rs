@@ -0,0 +1,49 @@
+//! Proving that a blob is included in the block of the head of a sync proof.
+use crate::sync::accepted_values;
+use anyhow::{anyhow, bail, Result};
+use cryptographic_sync_common::{BlobInclusionProof, BlobInclusionPublicValues};
+use sp1_sdk::{HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
+use std::{fs, path::Path};
+
+pub const BLOB_ELF: &[u8] = include_bytes!("../../blob-program/elf/riscv32im-succinct-zkvm-elf");
+
+/// Read the shares and proofs of a blob, stored as JSON.
+pub fn read_blob(path: &Path) -> Result<BlobInclusionProof> {
+    let file = fs::File::open(path).map_err(|e| anyhow!("could not open {}: {}", path.display(), e))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Prove that `blob` is included in the block of the head of `sync_proof`.
+///
+/// The blob is checked against the head's data root before proving, so that a proof is only
+/// attempted for a blob the program accepts.
+pub fn prove_blob(
+    client: &ProverClient,
+    sync_vk: &SP1VerifyingKey,
+    sync_proof: &SP1ProofWithPublicValues,
+    blob: &BlobInclusionProof,
+) -> Result<SP1ProofWithPublicValues> {
+    let head = accepted_values(sync_proof)?.head;
+    let data_hash = head.data_hash.ok_or_else(|| anyhow!("header {} has no data hash", head.height))?;
+    blob.verify(&data_hash).map_err(|e| anyhow!("blob is not included in header {}: {}", head.height, e))?;
+
+    let (pk, _) = client.setup(BLOB_ELF);
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&sync_vk.hash_u32());
+    stdin.write(&sync_proof.public_values.to_vec());
+    stdin.write(blob);
+    let sync_proof_inner = *match sync_proof.proof.clone() {
+        SP1Proof::Compressed(c) => c,
+        _ => bail!("sync proof is not a compressed proof"),
+    };
+    stdin.write_proof(sync_proof_inner, sync_vk.vk.clone());
+
+    let proof = client.prove(&pk, stdin).compressed().run()?;
+    let public_values = BlobInclusionPublicValues::decode(&proof.public_values.to_vec())?;
+    println!(
+        "proved the blob with commitment {:?} is included in height {}",
+        public_values.commitment,
+        public_values.height
+    );
+    Ok(proof)
+}
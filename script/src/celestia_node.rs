@@ -0,0 +1,169 @@
+//! Fetching headers from the JSON-RPC API of a celestia-node bridge or full node.
+use crate::{
+    convert,
+    tm_rpc_utils::{http_client, with_retries, RpcConfig, RpcError},
+};
+use anyhow::Result;
+use celestia_types::ExtendedHeader;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use tendermint::node::Id;
+use tendermint_light_client_verifier::types::LightBlock;
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    message: String,
+}
+
+pub struct CelestiaNodeClient {
+    url: String,
+    /// The node's auth token, needed unless the node runs with `--rpc.skip-auth`.
+    auth_token: Option<String>,
+    client: Client,
+    config: RpcConfig,
+}
+
+impl CelestiaNodeClient {
+    pub fn new(url: String, auth_token: Option<String>, config: RpcConfig) -> Self {
+        let client = http_client(&config);
+        CelestiaNodeClient { url, auth_token, client, config }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Calls a JSON-RPC method, retrying transient failures with exponential backoff.
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: serde_json::Value) -> Result<T, RpcError> {
+        with_retries(&self.config, || self.try_call(method, params.clone())).await
+    }
+
+    async fn try_call<T: DeserializeOwned>(&self, method: &str, params: serde_json::Value) -> Result<T, RpcError> {
+        let url = self.url.clone();
+        let request_error = |source: reqwest::Error| {
+            if source.is_timeout() {
+                RpcError::Timeout { url: url.clone() }
+            } else {
+                RpcError::Request { url: url.clone(), source }
+            }
+        };
+        let mut request = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }));
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(request_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RpcError::Status { url, status });
+        }
+        let body = response.bytes().await.map_err(request_error)?;
+        let response: JsonRpcResponse<T> =
+            serde_json::from_slice(&body).map_err(|e| RpcError::Decode { url: url.clone(), reason: e.to_string() })?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(RpcError::Remote { url, message: error.message }),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(RpcError::Malformed(format!("{} returned neither a result nor an error", method))),
+        }
+    }
+
+    /// Fetches the extended header at `height`.
+    pub async fn fetch_extended_header(&self, height: u64) -> Result<ExtendedHeader, RpcError> {
+        self.call("header.GetByHeight", json!([height])).await
+    }
+
+    /// Fetches the chain ID of the node's network head.
+    pub async fn fetch_chain_id(&self) -> Result<String, RpcError> {
+        let head: ExtendedHeader = self.call("header.NetworkHead", json!([])).await?;
+        Ok(head.header.chain_id.to_string())
+    }
+
+    /// Fetches the light block at `height`.
+    ///
+    /// An extended header does not hold the next validator set, which is taken from the extended
+    /// header of the next height.
+    pub async fn fetch_light_block(&self, height: u64) -> Result<LightBlock> {
+        let header = self.fetch_extended_header(height).await?;
+        let next = self.fetch_extended_header(height + 1).await?;
+        // celestia-node does not expose a Tendermint peer ID
+        convert::to_light_block(&header, next.validator_set, Id::new(convert::UNKNOWN_PROVIDER))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tm_rpc_utils::tests::{mock_server_with, quick_config};
+    use std::{
+        fs,
+        sync::{Arc, Mutex},
+    };
+
+    /// Serve the given `(status, body)` responses in order, one per request, and return the URL
+    /// along with the requests received, headers and body.
+    async fn mock_node(responses: Vec<(u16, String)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let received = requests.clone();
+        let mut responses = responses.into_iter();
+        let url = mock_server_with(move |request| {
+            received.lock().unwrap().push(request.to_string());
+            responses.next()
+        })
+        .await;
+        (url, requests)
+    }
+
+    fn result(file: &str) -> (u16, String) {
+        (200, format!(r#"{{"jsonrpc":"2.0","id":1,"result":{}}}"#, fs::read_to_string(file).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_fetches_light_blocks_with_the_next_validators() {
+        let (url, requests) = mock_node(vec![result("zkgenesis.json"), result("nethead2.json")]).await;
+        let client = CelestiaNodeClient::new(url, Some("secret".to_string()), quick_config());
+        let block = client.fetch_light_block(1798987).await.unwrap();
+        assert_eq!(block.height().value(), 1798987);
+        assert_eq!(block.signed_header.header().next_validators_hash, block.next_validators.hash());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        for (request, height) in requests.iter().zip([1798987, 1798988]) {
+            let lowercase = request.to_lowercase();
+            assert!(lowercase.contains("authorization: bearer secret"), "{}", request);
+            let body: serde_json::Value = serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap();
+            assert_eq!(body["method"], "header.GetByHeight");
+            assert_eq!(body["params"], json!([height]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_maps_errors() {
+        let not_found = r#"{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"header: not found"}}"#;
+        let (url, requests) = mock_node(vec![(200, not_found.to_string())]).await;
+        let client = CelestiaNodeClient::new(url, None, quick_config());
+        let error = client.fetch_extended_header(5).await.unwrap_err();
+        assert!(matches!(&error, RpcError::Remote { message, .. } if message == "header: not found"), "{}", error);
+        assert!(
+            !requests.lock().unwrap()[0].to_lowercase().contains("authorization"),
+            "no token is sent without one"
+        );
+
+        let (url, _) = mock_node(vec![(401, "{}".to_string())]).await;
+        let client = CelestiaNodeClient::new(url, Some("wrong".to_string()), quick_config());
+        let error = client.fetch_extended_header(5).await.unwrap_err();
+        assert!(matches!(error, RpcError::Status { status, .. } if status == 401));
+
+        let (url, _) = mock_node(vec![(200, r#"{"jsonrpc":"2.0","id":1}"#.to_string())]).await;
+        let client = CelestiaNodeClient::new(url, None, quick_config());
+        assert!(matches!(client.fetch_extended_header(5).await, Err(RpcError::Malformed(_))));
+    }
+}
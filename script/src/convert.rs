@@ -0,0 +1,186 @@
+//! Conversions between celestia-node `ExtendedHeader`s and tendermint `LightBlock`s.
+//!
+//! Neither holds all of the other: an extended header has no next validator set and a light block
+//! has no data availability header. Each conversion takes the missing part, so that converting
+//! back and forth loses nothing. A light block also names the peer it was fetched from, which an
+//! extended header does not record; it is taken back too, or is [`UNKNOWN_PROVIDER`].
+use anyhow::{anyhow, ensure, Context, Result};
+use celestia_types::{nmt::{NamespacedHash, NamespacedHashExt}, DataAvailabilityHeader, ExtendedHeader};
+use cryptographic_sync_common::DataAvailabilityRoots;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+use tendermint::{block::signed_header::SignedHeader, node::Id, validator::Set};
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// The provider of light blocks whose peer is not known, such as those of extended header files or
+/// of celestia-node endpoints. Verification does not use the provider.
+pub const UNKNOWN_PROVIDER: [u8; 20] = [0; 20];
+
+/// The light block of an extended header, given the validator set of the next height and the peer
+/// it was fetched from.
+pub fn to_light_block(header: &ExtendedHeader, next_validators: Set, provider: Id) -> Result<LightBlock> {
+    ensure!(
+        next_validators.hash() == header.header.next_validators_hash,
+        "the next validators do not match header {}",
+        header.header.height
+    );
+    let signed_header = SignedHeader::new(header.header.clone(), header.commit.clone())?;
+    Ok(LightBlock::new(signed_header, header.validator_set.clone(), next_validators, provider))
+}
+
+/// The extended header of a light block, given the data availability header of its block.
+///
+/// The light block's provider is dropped, as an extended header has no place for it.
+pub fn to_extended_header(block: &LightBlock, dah: DataAvailabilityHeader) -> ExtendedHeader {
+    ExtendedHeader {
+        header: block.signed_header.header().clone(),
+        commit: block.signed_header.commit().clone(),
+        validator_set: block.validators.clone(),
+        dah,
+    }
+}
+
+/// Data availability roots of headers, by height.
+pub type Dahs = BTreeMap<u64, DataAvailabilityRoots>;
+
+/// The roots of a data availability header, as the sync program takes them.
+pub fn data_availability_roots(dah: &DataAvailabilityHeader) -> DataAvailabilityRoots {
+    let roots = |roots: &[NamespacedHash]| roots.iter().map(|root| root.to_array().to_vec()).collect();
+    DataAvailabilityRoots {
+        square_size: dah.row_roots().len() as u64,
+        row_roots: roots(dah.row_roots()),
+        column_roots: roots(dah.column_roots()),
+    }
+}
+
+/// A header file of either shape: `needed_headers/*.json` hold light blocks, while headers saved
+/// from celestia-node, like `zkgenesis.json`, are extended headers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HeaderFile {
+    Light(Box<LightBlock>),
+    Extended(Box<ExtendedHeader>),
+}
+
+impl HeaderFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+        serde_json::from_reader(file).map_err(|e| anyhow!("{} is neither a light block nor an extended header: {}", path.display(), e))
+    }
+
+    pub fn height(&self) -> u64 {
+        match self {
+            HeaderFile::Light(block) => block.height().value(),
+            HeaderFile::Extended(header) => header.header.height.value(),
+        }
+    }
+
+    pub fn chain_id(&self) -> String {
+        match self {
+            HeaderFile::Light(block) => block.signed_header.header().chain_id.to_string(),
+            HeaderFile::Extended(header) => header.header.chain_id.to_string(),
+        }
+    }
+
+    /// The data availability roots of the header, which only an extended header holds.
+    pub fn dah(&self) -> Option<DataAvailabilityRoots> {
+        match self {
+            HeaderFile::Light(_) => None,
+            HeaderFile::Extended(header) => Some(data_availability_roots(&header.dah)),
+        }
+    }
+
+    /// The validators of the header's own height.
+    pub fn validators(&self) -> &Set {
+        match self {
+            HeaderFile::Light(block) => &block.validators,
+            HeaderFile::Extended(header) => &header.validator_set,
+        }
+    }
+
+    /// The light block of the header; an extended header takes its next validators from `next`,
+    /// the header of the following height, and has an unknown provider.
+    pub fn into_light_block(self, next: Option<&HeaderFile>) -> Result<LightBlock> {
+        match self {
+            HeaderFile::Light(block) => Ok(*block),
+            HeaderFile::Extended(header) => {
+                let height = header.header.height.value();
+                let next = next
+                    .filter(|next| next.height() == height + 1)
+                    .ok_or_else(|| anyhow!("extended header {} needs the header {} for its next validators", height, height + 1))?;
+                to_light_block(&header, next.validators().clone(), Id::new(UNKNOWN_PROVIDER))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extended(file: &str) -> ExtendedHeader {
+        match HeaderFile::read(Path::new(file)).unwrap() {
+            HeaderFile::Extended(header) => *header,
+            HeaderFile::Light(_) => panic!("{} is not an extended header", file),
+        }
+    }
+
+    #[test]
+    fn test_round_trips() {
+        // zkgenesis.json is the mocha-4 header just before nethead2.json
+        let genesis = extended("zkgenesis.json");
+        let next = extended("nethead2.json");
+        let block = to_light_block(&genesis, next.validator_set.clone(), Id::new([0; 20])).unwrap();
+        assert_eq!(block.signed_header.header().hash(), genesis.header.hash());
+
+        let back = to_extended_header(&block, genesis.dah.clone());
+        assert_eq!(back, genesis);
+        let again = to_light_block(&back, next.validator_set.clone(), Id::new([0; 20])).unwrap();
+        assert_eq!(again, block);
+
+        // The validators of a later height are not the next validators
+        let skip = extended("skipheader.json");
+        let mismatched = HeaderFile::Extended(Box::new(genesis.clone())).into_light_block(Some(&HeaderFile::Extended(Box::new(skip))));
+        assert!(mismatched.is_err());
+    }
+
+    #[test]
+    fn test_light_blocks_round_trip_with_their_provider() {
+        let block = match HeaderFile::read(Path::new("needed_headers/75845.json")).unwrap() {
+            HeaderFile::Light(block) => *block,
+            HeaderFile::Extended(_) => panic!("needed_headers/75845.json is not a light block"),
+        };
+        assert_ne!(block.provider, Id::new(UNKNOWN_PROVIDER));
+
+        // Any data availability header will do, the conversion does not check it
+        let dah = extended("zkgenesis.json").dah;
+        let header = to_extended_header(&block, dah.clone());
+        assert_eq!(header.header.hash(), block.signed_header.header().hash());
+        assert_eq!(header.dah, dah);
+        let back = to_light_block(&header, block.next_validators.clone(), block.provider).unwrap();
+        assert_eq!(back, block);
+    }
+
+    #[test]
+    fn test_header_files_of_either_shape() {
+        let light = HeaderFile::read(Path::new("needed_headers/75845.json")).unwrap();
+        assert!(matches!(light, HeaderFile::Light(_)));
+        assert_eq!((light.height(), light.chain_id().as_str()), (75845, "celestia"));
+
+        let genesis = HeaderFile::read(Path::new("zkgenesis.json")).unwrap();
+        let next = HeaderFile::read(Path::new("nethead2.json")).unwrap();
+        assert_eq!(genesis.chain_id(), "mocha-4");
+        let block = genesis.into_light_block(Some(&next)).unwrap();
+        assert_eq!(block.next_validators.hash(), block.signed_header.header().next_validators_hash);
+    }
+
+    #[test]
+    fn test_data_availability_roots_hash_to_data_hash() {
+        let genesis = extended("zkgenesis.json");
+        let roots = data_availability_roots(&genesis.dah);
+        assert!(roots.is_well_formed());
+        let data_hash = genesis.header.data_hash.unwrap();
+        assert_eq!(roots.hash().as_slice(), data_hash.as_bytes());
+        assert!(HeaderFile::read(Path::new("needed_headers/75845.json")).unwrap().dah().is_none());
+    }
+}
@@ -0,0 +1,79 @@
+//! Executing planned steps without proving them, to catch failing inputs and size the work early.
+use crate::{aggregate, convert::Dahs};
+use anyhow::{anyhow, bail, Result};
+use cryptographic_sync_common::{SyncPublicValues, Timestamp, TrustPolicy};
+use sp1_sdk::{ProverClient, SP1VerifyingKey};
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// Cycles spent verifying one header from the header before it.
+#[derive(Debug, Clone)]
+pub struct GapReport {
+    pub from: u64,
+    pub to: u64,
+    pub cycles: u64,
+}
+
+/// Cycles spent by one step, in total and per verified header.
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub first: u64,
+    pub last: u64,
+    pub cycles: u64,
+    pub gaps: Vec<GapReport>,
+}
+
+/// Execute every step of `headers[0] -> headers[last]` with steps of `step_size` transitions.
+///
+/// Each step is executed from its own trusted header with the same input as a proved segment,
+/// since the proof a recursive step would verify does not exist yet; the proof verification this
+/// leaves out costs the same for every step. Stops at the first step the program panics on or
+/// rejects.
+pub fn execute_range(
+    client: &ProverClient,
+    vk: &SP1VerifyingKey,
+    policy: &TrustPolicy,
+    headers: &[LightBlock],
+    dahs: &Dahs,
+    step_size: usize,
+) -> Result<Vec<StepReport>> {
+    let mut reports = vec![];
+    for segment in aggregate::segments(headers, step_size) {
+        let first = segment[0].height().value();
+        let last = segment.last().expect("segments are not empty").height().value();
+        let stdin = aggregate::segment_stdin(vk, policy, &segment, dahs.get(&last), &Timestamp::now())?;
+        let (public_values, report) = client
+            .execute(crate::ELF, stdin)
+            .run()
+            .map_err(|e| anyhow!("step {} -> {} failed to execute: {}", first, last, e))?;
+        let executed = SyncPublicValues::decode(public_values.as_slice())?;
+        if let Some(rejection) = &executed.rejection {
+            bail!("step {} -> {} rejected: {}", first, last, rejection);
+        }
+
+        let gaps = segment
+            .windows(2)
+            .map(|pair| {
+                let to = pair[1].height().value();
+                GapReport {
+                    from: pair[0].height().value(),
+                    to,
+                    cycles: report.cycle_tracker.get(&format!("header {}", to)).copied().unwrap_or_default(),
+                }
+            })
+            .collect();
+        reports.push(StepReport { first, last, cycles: report.total_instruction_count(), gaps });
+    }
+    Ok(reports)
+}
+
+/// Print the cycle counts of executed steps.
+pub fn print_reports(reports: &[StepReport]) {
+    for step in reports {
+        println!("step {} -> {}: {} cycles", step.first, step.last, step.cycles);
+        for gap in &step.gaps {
+            println!("  {} -> {} (gap of {}): {} cycles", gap.from, gap.to, gap.to - gap.from, gap.cycles);
+        }
+    }
+    let total: u64 = reports.iter().map(|step| step.cycles).sum();
+    println!("{} steps, {} cycles in total", reports.len(), total);
+}
@@ -0,0 +1,99 @@
+//! Inclusion proofs of historical headers in the accumulator committed by a sync proof.
+use crate::convert::{Dahs, HeaderFile};
+use anyhow::{anyhow, ensure, Result};
+use cryptographic_sync_common::{Mmr, MmrProof, SyncPublicValues};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// Proof that the header at `height` with hash `hash` was verified by a sync proof.
+///
+/// Only the hash is accumulated; it commits to the whole header, including its height.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderInclusion {
+    pub height: u64,
+    pub hash: [u8; 32],
+    pub proof: MmrProof,
+}
+
+/// Heights of the `<height>.json` header files in `dir`, sorted.
+pub fn header_heights(dir: &Path) -> Result<Vec<u64>> {
+    let mut heights = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().unwrap_or_default() == "json" {
+            if let Some(height) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse().ok()) {
+                heights.push(height);
+            }
+        }
+    }
+    heights.sort();
+    Ok(heights)
+}
+
+/// Read the header of the given height from `dir`.
+///
+/// The file may hold a light block or a celestia-node extended header, which takes its next
+/// validators from the file of the next height.
+pub fn read_header(dir: &Path, height: u64) -> Result<LightBlock> {
+    let header = HeaderFile::read(&dir.join(format!("{}.json", height)))?;
+    let next_path = dir.join(format!("{}.json", height + 1));
+    let next = match &header {
+        HeaderFile::Extended(_) if next_path.exists() => Some(HeaderFile::read(&next_path)?),
+        _ => None,
+    };
+    header.into_light_block(next.as_ref())
+}
+
+/// The data availability roots of every extended header file in `dir`.
+pub fn read_dahs(dir: &Path) -> Result<Dahs> {
+    let mut dahs = Dahs::new();
+    for height in header_heights(dir)? {
+        if let Some(dah) = HeaderFile::read(&dir.join(format!("{}.json", height)))?.dah() {
+            dahs.insert(height, dah);
+        }
+    }
+    Ok(dahs)
+}
+
+/// Rebuild the heights and hashes of every header accumulated by a sync proof.
+///
+/// The start of the proof is taken from its public values, the following headers are read from
+/// the header files in `dir` up to the proof's head, and the rebuilt accumulator must match the
+/// committed one exactly.
+pub fn accumulated_headers(public_values: &SyncPublicValues, dir: &Path) -> Result<Vec<(u64, [u8; 32])>> {
+    let mut headers = vec![(public_values.start.height, public_values.start.hash)];
+    for height in header_heights(dir)? {
+        if height <= public_values.start.height || height > public_values.head.height {
+            continue;
+        }
+        let header = read_header(dir, height)?;
+        let hash = header.signed_header.header().hash().as_bytes().try_into()?;
+        headers.push((height, hash));
+    }
+
+    let leaves: Vec<[u8; 32]> = headers.iter().map(|(_, hash)| *hash).collect();
+    ensure!(
+        Mmr::from_leaves(&leaves) == public_values.history,
+        "the headers in {} do not match the accumulator of the proof",
+        dir.display()
+    );
+    Ok(headers)
+}
+
+/// Prove that the header at `height` was verified by the sync proof with these public values.
+pub fn prove_inclusion(public_values: &SyncPublicValues, dir: &Path, height: u64) -> Result<HeaderInclusion> {
+    let headers = accumulated_headers(public_values, dir)?;
+    let index = headers
+        .iter()
+        .position(|(h, _)| *h == height)
+        .ok_or_else(|| anyhow!("height {} was not verified by the proof", height))?;
+    let leaves: Vec<[u8; 32]> = headers.iter().map(|(_, hash)| *hash).collect();
+    let proof = Mmr::prove(&leaves, index as u64).expect("index is in range");
+    Ok(HeaderInclusion { height, hash: leaves[index], proof })
+}
+
+/// Check an inclusion proof against the accumulator root of a sync proof's public values.
+pub fn verify_inclusion(public_values: &SyncPublicValues, inclusion: &HeaderInclusion) -> bool {
+    inclusion.proof.verify(&inclusion.hash, &public_values.history.root())
+}
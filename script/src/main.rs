@@ -1,83 +1,614 @@
-use core::time::Duration;
-use serde_json;
+//! Command line interface for syncing, proving and inspecting Celestia header proofs.
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use cryptographic_sync_common::{AncestryPublicValues, BlobInclusionPublicValues, SyncPublicValues, TrustPolicy};
+use sp1_sdk::ProverClient;
 use std::{
-    io::Write,
-    fs, 
-    path::Path,
-    time::Instant,
-};
-use tendermint_light_client_verifier::{
-    options::Options, types::LightBlock, ProdVerifier, Verdict, Verifier,
+    fs,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Duration,
 };
+use tendermint_light_client_verifier::types::LightBlock;
+mod aggregate;
+mod ancestry;
+mod blob;
+mod celestia_node;
+mod convert;
+mod dry_run;
+mod fixtures;
+mod history;
+mod quorum;
+mod schedule;
+mod source;
+mod store;
+mod sync;
 mod tm_rpc_utils;
 mod tm_rpc_types;
-use sp1_sdk::{HashableKey, SP1VerifyingKey};
-use sp1_sdk::{SP1Proof, SP1ProofWithPublicValues};
-use sp1_sdk::{ProverClient, SP1Stdin};
+use celestia_node::CelestiaNodeClient;
+use convert::{Dahs, HeaderFile};
+use quorum::QuorumClient;
+use source::{Endpoint, HeaderSource};
+use store::HeaderStore;
+use tm_rpc_utils::{RpcConfig, TendermintRPCClient};
 
 pub const ELF: &[u8] = include_bytes!("../../program/elf/riscv32im-succinct-zkvm-elf");
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    println!("creating rpc client");
-    let client = tm_rpc_utils::TendermintRPCClient::default();
-    let peer_id = client.fetch_peer_id().await.unwrap();
-    println!("getting genesis...");
-    let genesis = client.fetch_light_block(1, peer_id).await.unwrap();
-
-    let dir = fs::read_dir("needed_headers")?;
-    let mut files = vec![];
-    for entry in dir {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file() && path.extension().unwrap_or_default() == "json" {
-            let filename = path
-                .file_stem()
-                .unwrap_or_default()
-                .to_str()
-                .unwrap_or_default();
-            files.push(filename.to_string());
-        }
-    }
-    files.sort_by(|a, b| a.parse::<u32>().unwrap().cmp(&b.parse::<u32>().unwrap()));
-
-    let left_off_proof_file = std::fs::File::open("1015226_proof.json").expect("could not open left_off_proof.json");
-    let mut running_proof: SP1ProofWithPublicValues = serde_json::from_reader(left_off_proof_file).expect("could not parse");
-
-    let running_header_file = std::fs::File::open("needed_headers/1015226.json").unwrap();
-    let mut running_head: Option<LightBlock> = serde_json::from_reader(running_header_file).unwrap();
-
-    // header where i got booted off wifi
-    let left_off: String = "1015226".to_string();
-    let start = files.iter().position(|r| *r == left_off).unwrap()+1;
-
-    for i in start..files.len() {
-        let prover_client = ProverClient::new();
-        let (pk, vk) = prover_client.setup(ELF);
-        let running_proof_public_values = running_proof.public_values.to_vec();
-        let mut stdin = SP1Stdin::new();
-        stdin.write(&vk.hash_u32());
-        stdin.write(&running_proof_public_values);
-        stdin.write_vec(genesis.clone().signed_header.header().hash().as_bytes().to_vec());
-        let encoded1 = serde_cbor::to_vec(&running_head).expect("failed to serialzie running head");
-        stdin.write_vec(encoded1);
-        let next_header_file = std::fs::File::open(format!("needed_headers/{}.json",&files[i])).expect("Could not open");
-        let next_header: Option<LightBlock> = Some(serde_json::from_reader(next_header_file).expect("could not parse"));
-        let encoded2 = serde_cbor::to_vec(&next_header).expect("coudl not serialize");
-        stdin.write_vec(encoded2);
-        let running_proof_inner = *match running_proof.proof.clone() {
-            SP1Proof::Compressed(c) => c,
-            _ => panic!("Not the right kind of SP1 proof")
+#[derive(Parser)]
+#[command(about = "Prove Celestia headers from a trusted start, one batch or one tree at a time")]
+struct Cli {
+    /// RPC endpoints headers are fetched from, comma separated.
+    #[arg(long, global = true, env = "TENDERMINT_RPC_URL", value_delimiter = ',')]
+    rpc_url: Vec<String>,
+    /// Record every RPC response to this directory as a fixture, with the cometbft source only.
+    #[arg(long, global = true)]
+    record: Option<PathBuf>,
+    /// Serve RPC requests from the fixtures in this directory, in place of any cometbft endpoint.
+    #[arg(long, global = true)]
+    replay: Option<PathBuf>,
+    /// Number of endpoints that must return the same header, by default a majority of them.
+    #[arg(long, global = true)]
+    quorum: Option<usize>,
+    /// The kind of API the endpoints speak.
+    #[arg(long, global = true, value_enum, env = "HEADER_SOURCE", default_value_t = Source::CometBft)]
+    source: Source,
+    /// Auth token of celestia-node endpoints.
+    #[arg(long, global = true, env = "CELESTIA_NODE_AUTH_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+    /// Timeout of each RPC request, in seconds.
+    #[arg(long, global = true, default_value_t = 30)]
+    rpc_timeout_secs: u64,
+    /// Number of times a failed RPC request is retried, with exponential backoff.
+    #[arg(long, global = true, default_value_t = 5)]
+    rpc_retries: u32,
+    /// Chain ID of the header store, by default the only cached chain or the endpoint's chain.
+    #[arg(long, global = true, env = "CHAIN_ID")]
+    chain_id: Option<String>,
+    /// Directory every fetched light block is cached in, by chain ID and height.
+    #[arg(long, global = true, default_value = "header_store")]
+    store: PathBuf,
+    /// Directory of `<height>.json` light blocks.
+    #[arg(long, global = true, default_value = "needed_headers")]
+    headers_dir: PathBuf,
+    /// Directory proofs are written to.
+    #[arg(long, global = true, default_value = ".")]
+    out_dir: PathBuf,
+    /// Number of headers verified by each step, at least one.
+    #[arg(long, global = true, default_value = "8")]
+    batch_size: NonZeroUsize,
+    /// How long a verified header can be trusted for, in seconds.
+    #[arg(long, global = true, default_value_t = TrustPolicy::default().trusting_period.as_secs())]
+    trusting_period_secs: u64,
+    /// Numerator of the fraction of the trusted validators' power that must sign a new header.
+    #[arg(long, global = true, default_value_t = TrustPolicy::default().trust_threshold_numerator)]
+    trust_threshold_numerator: u64,
+    /// Denominator of the trust threshold fraction.
+    #[arg(long, global = true, default_value_t = TrustPolicy::default().trust_threshold_denominator)]
+    trust_threshold_denominator: u64,
+    /// Tolerated clock drift between the header time and the verification time, in seconds.
+    #[arg(long, global = true, default_value_t = TrustPolicy::default().clock_drift.as_secs())]
+    clock_drift_secs: u64,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Fetch light blocks into the headers directory.
+    Fetch {
+        heights: Vec<u64>,
+    },
+    /// Print the steps that proving from `start` would take.
+    Plan {
+        #[arg(long, default_value_t = 1)]
+        start: u64,
+        /// Fetch the largest skips from `start` up to this height into the headers directory first.
+        #[arg(long)]
+        target: Option<u64>,
+    },
+    /// Write RPC fixtures from light block or celestia-node extended header files.
+    Fixtures {
+        /// Directory the fixtures are written to.
+        dir: PathBuf,
+        files: Vec<PathBuf>,
+    },
+    /// Check every transition from `start` through the headers directory without proving.
+    Validate {
+        #[arg(long, default_value_t = 1)]
+        start: u64,
+    },
+    /// Prove every header in the headers directory after a trusted start header.
+    ///
+    /// In linear mode, a valid proof from the same start in the output directory is resumed from
+    /// instead of starting over.
+    Prove {
+        /// Height of the trusted start header, fetched if it is not in the headers directory.
+        #[arg(long, default_value_t = 1)]
+        start: u64,
+        #[arg(long, value_enum, default_value_t = Mode::Linear)]
+        mode: Mode,
+        /// Number of proofs proved at the same time in tree mode.
+        #[arg(long, default_value = "1")]
+        jobs: NonZeroUsize,
+        /// Execute the steps and report their cycles instead of proving them.
+        #[arg(long)]
+        execute_only: bool,
+    },
+    /// Extend an existing proof with the headers after its head.
+    Resume {
+        /// The proof to extend, by default the newest valid proof in the output directory.
+        proof: Option<PathBuf>,
+        /// Execute the steps and report their cycles instead of proving them.
+        #[arg(long)]
+        execute_only: bool,
+    },
+    /// Verify a proof of the sync, ancestry or blob inclusion program.
+    Verify {
+        proof: PathBuf,
+        #[arg(long, value_enum, default_value_t = Program::Sync)]
+        program: Program,
+    },
+    /// Print the public values of a proof.
+    Inspect {
+        proof: PathBuf,
+    },
+    /// Prove that a header was verified by a sync proof, from its committed accumulator.
+    Inclusion {
+        proof: PathBuf,
+        height: u64,
+    },
+    /// Prove that an older header is an ancestor of the head of a sync proof.
+    Ancestry {
+        proof: PathBuf,
+        height: u64,
+    },
+    /// Prove that a blob is included in the block of the head of a sync proof.
+    Blob {
+        proof: PathBuf,
+        /// JSON file of the blob's namespace, shares, and row and share proofs.
+        blob: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    /// One recursive proof per batch, each extending the previous one.
+    Linear,
+    /// Independent segment proofs merged pairwise into a single range proof.
+    Tree,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Source {
+    /// CometBFT RPC, as served by celestia-app consensus nodes.
+    #[value(name = "cometbft")]
+    CometBft,
+    /// celestia-node JSON-RPC, as served by bridge and full nodes.
+    CelestiaNode,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Program {
+    Sync,
+    Ancestry,
+    Blob,
+}
+
+impl Cli {
+    /// The trust policy given on the command line, refused if the light client would refuse it.
+    fn policy(&self) -> Result<TrustPolicy> {
+        let policy = TrustPolicy {
+            trusting_period: Duration::from_secs(self.trusting_period_secs),
+            trust_threshold_numerator: self.trust_threshold_numerator,
+            trust_threshold_denominator: self.trust_threshold_denominator,
+            clock_drift: Duration::from_secs(self.clock_drift_secs),
+        };
+        schedule::options(&policy)?;
+        Ok(policy)
+    }
+
+    /// The client of the endpoints given on the command line, or `None` without any, to only
+    /// use cached headers.
+    fn optional_rpc_client(&self) -> Result<Option<QuorumClient>> {
+        if self.rpc_url.is_empty() {
+            return Ok(None);
+        }
+        self.rpc_client().map(Some)
+    }
+
+    fn rpc_client(&self) -> Result<QuorumClient> {
+        if self.rpc_url.is_empty() {
+            bail!("no RPC endpoint, set --rpc-url or TENDERMINT_RPC_URL");
+        }
+        let config = RpcConfig {
+            timeout: Duration::from_secs(self.rpc_timeout_secs),
+            max_retries: self.rpc_retries,
+            ..RpcConfig::default()
         };
-        stdin.write_proof(running_proof_inner, vk.vk.clone());
-        println!("creating proof for {}", files[i]);
-        running_proof = prover_client.prove(&pk, stdin).groth16().run().expect("could not prove");
-        std::fs::write(format!("{}_groth16_proof.json", files[i]), serde_json::to_string(&running_proof).expect("could not json serialize")).expect("could not write");
-        println!("the vkey: {:?}", vk.vk);
-        return Ok(());
-        running_head = next_header;
+        let endpoints = self
+            .rpc_url
+            .iter()
+            .map(|url| match self.source {
+                Source::CometBft => {
+                    let client = TendermintRPCClient::with_config(url.clone(), config.clone());
+                    Endpoint::CometBft(match &self.record {
+                        Some(dir) => client.recording(dir.clone()),
+                        None => client,
+                    })
+                }
+                Source::CelestiaNode => {
+                    Endpoint::CelestiaNode(CelestiaNodeClient::new(url.clone(), self.auth_token.clone(), config.clone()))
+                }
+            })
+            .collect();
+        QuorumClient::new(endpoints, self.quorum.unwrap_or(self.rpc_url.len() / 2 + 1))
+    }
+
+    /// The header store of the chain given on the command line, of the only cached chain, or of
+    /// the chain the RPC endpoint is on, in that order.
+    async fn header_store(&self) -> Result<HeaderStore> {
+        let chain_id = match &self.chain_id {
+            Some(chain_id) => chain_id.clone(),
+            None => match store::cached_chain_ids(&self.store)?.as_slice() {
+                [chain_id] => chain_id.clone(),
+                _ => self.rpc_client()?.fetch_chain_id().await?,
+            },
+        };
+        HeaderStore::open(&self.store, &chain_id)
+    }
+
+    /// The header at `height`, from the headers directory or else from the header store.
+    async fn header(&self, store: &HeaderStore, client: Option<&QuorumClient>, height: u64) -> Result<LightBlock> {
+        if self.headers_dir.join(format!("{}.json", height)).exists() {
+            return history::read_header(&self.headers_dir, height);
+        }
+        store.get_or_fetch(client, height).await
+    }
+}
+
+/// Every header in `dir` after `height`, in order.
+fn headers_after(dir: &Path, height: u64) -> Result<Vec<LightBlock>> {
+    history::header_heights(dir)?
+        .into_iter()
+        .filter(|h| *h > height)
+        .map(|h| history::read_header(dir, h))
+        .collect()
+}
+
+/// The data availability headers to prove heads with, read from the extended header files in
+/// `dir` when the sync program is built with its `dah` feature.
+fn head_dahs(dir: &Path) -> Result<Dahs> {
+    if cfg!(feature = "dah") {
+        history::read_dahs(dir)
+    } else {
+        Ok(Dahs::new())
+    }
+}
+
+/// The headers to prove of `headers`, checked against the data availability headers of `dahs`
+/// when the sync program is built with its `dah` feature.
+///
+/// An extended header file takes its next validators from the file of the height after it, which
+/// is a header to prove like any other, except after the last extended header, where it is only
+/// there to complete it. Every batch head must then be an extended header.
+fn dah_headers(mut headers: Vec<LightBlock>, dahs: &Dahs, batch_size: usize) -> Result<Vec<LightBlock>> {
+    if !cfg!(feature = "dah") {
+        return Ok(headers);
+    }
+    if let [.., extended, last] = headers.as_slice() {
+        let (extended, last) = (extended.height().value(), last.height().value());
+        if last == extended + 1 && dahs.contains_key(&extended) && !dahs.contains_key(&last) {
+            headers.pop();
+        }
+    }
+    for batch in headers.chunks(batch_size) {
+        let head = batch.last().expect("chunks are not empty").height().value();
+        if !dahs.contains_key(&head) {
+            bail!("batch head {} has no extended header file, which the dah feature needs", head);
+        }
+    }
+    Ok(headers)
+}
+
+/// Fail before proving if any transition of `headers` would be rejected by the program.
+fn ensure_valid_schedule(headers: &[LightBlock], policy: &TrustPolicy) -> Result<()> {
+    let failures = schedule::validate_schedule(headers, policy, schedule::now()?)?;
+    for failure in &failures {
+        println!("{} -> {}: {}", failure.from, failure.to, failure.reason);
+    }
+    if !failures.is_empty() {
+        bail!("{} of {} transitions would be rejected", failures.len(), headers.len().saturating_sub(1));
+    }
+    Ok(())
+}
+
+fn print_sync_values(values: &SyncPublicValues) {
+    println!("vkey hash: {:?}", values.vkey_hash);
+    if let Some(rejection) = &values.rejection {
+        println!("rejected: {}", rejection);
+        return;
+    }
+    println!("start: height {} hash {:?}", values.start.height, values.start.hash);
+    println!("head: height {} hash {:?}", values.head.height, values.head.hash);
+    println!("head time: {:?}", values.head.time);
+    println!("chain id: {}", values.head.chain_id);
+    println!("verified at: {:?}", values.verified_at);
+    println!("policy: {:?}", values.policy);
+    println!("accumulated headers: {}, root {:?}", values.history.leaf_count, values.history.root());
+    if let Some(dah) = &values.head_dah {
+        println!("head data availability header: square size {}, hash {:?}", dah.square_size, dah.hash());
+    }
+}
+
+fn print_ancestry_values(values: &AncestryPublicValues) {
+    println!("sync vkey hash: {:?}", values.sync_vkey_hash);
+    println!("sync start: height {} hash {:?}", values.start.height, values.start.hash);
+    println!("chain id: {}", values.chain_id);
+    println!("sync head hash: {:?}", values.head_hash);
+    println!("ancestor: height {} hash {:?}", values.ancestor.height, values.ancestor.hash);
+}
 
+fn print_blob_values(values: &BlobInclusionPublicValues) {
+    println!("sync vkey hash: {:?}", values.sync_vkey_hash);
+    println!("sync start: height {} hash {:?}", values.start.height, values.start.hash);
+    println!("chain id: {}", values.chain_id);
+    println!("head: height {} hash {:?}", values.height, values.head_hash);
+    println!("data hash: {:?}", values.data_hash);
+    println!("namespace: {:?}", values.namespace);
+    println!("commitment: {:?}", values.commitment);
+}
+
+/// Write the RPC fixtures of header files, taking the status from the first one.
+fn write_fixtures(dir: &Path, files: &[PathBuf]) -> Result<()> {
+    let mut status_written = false;
+    for file in files {
+        let header = HeaderFile::read(file)?;
+        if !status_written {
+            let peer_id = match &header {
+                HeaderFile::Light(block) => block.provider.as_bytes().try_into()?,
+                HeaderFile::Extended(_) => convert::UNKNOWN_PROVIDER,
+            };
+            fixtures::write_status(dir, &header.chain_id(), peer_id)?;
+            status_written = true;
+        }
+        match &header {
+            HeaderFile::Light(block) => fixtures::write_light_block(dir, block)?,
+            HeaderFile::Extended(header) => fixtures::write_extended_header(dir, header)?,
+        }
+        println!("wrote the fixtures of {}", file.display());
     }
     Ok(())
+}
 
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut cli = Cli::parse();
+    let policy = cli.policy()?;
+    // Fixtures are CometBFT RPC responses, which celestia-node endpoints do not answer with
+    if matches!(cli.source, Source::CelestiaNode) && (cli.record.is_some() || cli.replay.is_some()) {
+        bail!("--record and --replay only work with --source cometbft");
+    }
+
+    // Stand in for the endpoints, for as long as the command runs
+    let _replay_server = match &cli.replay {
+        Some(dir) => {
+            let server = fixtures::FixtureServer::start(dir).await?;
+            cli.rpc_url = vec![server.url().to_string()];
+            Some(server)
+        }
+        None => None,
+    };
+
+    match &cli.command {
+        Command::Fetch { heights } => {
+            let store = cli.header_store().await?;
+            let rpc = cli.optional_rpc_client()?;
+            let mut fetched = vec![];
+            for height in heights {
+                fetched.push(store.get_or_fetch(rpc.as_ref(), *height).await?);
+            }
+            schedule::write_schedule(&cli.headers_dir, &fetched)?;
+        }
+        Command::Plan { start, target } => {
+            let store = cli.header_store().await?;
+            let rpc = cli.optional_rpc_client()?;
+            if let Some(target) = target {
+                let trusted = cli.header(&store, rpc.as_ref(), *start).await?;
+                let planned = schedule::plan_schedule(&store, rpc.as_ref(), trusted, *target, &policy, schedule::now()?).await?;
+                schedule::write_schedule(&cli.headers_dir, &planned)?;
+                println!("wrote {} headers to {}", planned.len(), cli.headers_dir.display());
+            }
+            let mut headers = vec![cli.header(&store, rpc.as_ref(), *start).await?];
+            headers.extend(headers_after(&cli.headers_dir, *start)?);
+            let segments = aggregate::segments(&headers, cli.batch_size.get());
+            for segment in &segments {
+                let heights: Vec<u64> = segment.iter().map(|h| h.height().value()).collect();
+                let largest_gap = heights.windows(2).map(|pair| pair[1] - pair[0]).max().unwrap_or_default();
+                println!(
+                    "{} -> {}: {} transitions, largest gap {}",
+                    heights[0],
+                    heights[heights.len() - 1],
+                    heights.len() - 1,
+                    largest_gap
+                );
+            }
+            println!("{} steps", segments.len());
+        }
+        Command::Fixtures { dir, files } => write_fixtures(dir, files)?,
+        Command::Validate { start } => {
+            let store = cli.header_store().await?;
+            let rpc = cli.optional_rpc_client()?;
+            let mut headers = vec![cli.header(&store, rpc.as_ref(), *start).await?];
+            headers.extend(headers_after(&cli.headers_dir, *start)?);
+            ensure_valid_schedule(&headers, &policy)?;
+            println!("all {} transitions verify", headers.len() - 1);
+        }
+        Command::Prove { start, mode, jobs, execute_only } => {
+            let store = cli.header_store().await?;
+            let rpc = cli.optional_rpc_client()?;
+            let trusted = cli.header(&store, rpc.as_ref(), *start).await?;
+            let dahs = head_dahs(&cli.headers_dir)?;
+            let headers = dah_headers(headers_after(&cli.headers_dir, *start)?, &dahs, cli.batch_size.get())?;
+            let client = ProverClient::new();
+            let (pk, vk) = client.setup(ELF);
+            if *execute_only {
+                let mut range = vec![trusted];
+                range.extend(headers);
+                dry_run::print_reports(&dry_run::execute_range(&client, &vk, &policy, &range, &dahs, cli.batch_size.get())?);
+                return Ok(());
+            }
+            match mode {
+                Mode::Linear => {
+                    // Pick up after an interrupted run of the same chain
+                    let start_hash = aggregate::leaves(std::slice::from_ref(&trusted))[0];
+                    let previous = sync::latest_proof(&client, &vk, &cli.out_dir, &cli.headers_dir, Some(&start_hash))?;
+                    let (previous, trusted, headers) = match previous {
+                        Some((path, proof, values)) => {
+                            let head = values.head.height;
+                            println!("resuming from {}", path.display());
+                            // The program only extends a proof under the policy it was made under
+                            sync::ensure_policy(&values, &policy)?;
+                            let trusted = history::read_header(&cli.headers_dir, head)?;
+                            (Some(proof), trusted, dah_headers(headers_after(&cli.headers_dir, head)?, &dahs, cli.batch_size.get())?)
+                        }
+                        None => (None, trusted, headers),
+                    };
+                    if headers.is_empty() {
+                        println!("nothing left to prove");
+                        return Ok(());
+                    }
+                    ensure_valid_schedule(&[&[trusted.clone()], &headers[..]].concat(), &policy)?;
+                    sync::prove_chain(&client, &pk, &vk, &policy, previous, trusted, &headers, &dahs, cli.batch_size.get(), &cli.out_dir)?;
+                }
+                Mode::Tree => {
+                    if headers.is_empty() {
+                        println!("nothing left to prove");
+                        return Ok(());
+                    }
+                    let mut range = vec![trusted];
+                    range.extend(headers);
+                    ensure_valid_schedule(&range, &policy)?;
+                    let proof = aggregate::prove_tree(&client, &pk, &vk, &policy, &range, &dahs, cli.batch_size.get(), *jobs)?;
+                    let proved = sync::accepted_values(&proof)?;
+                    println!("proved height {} to {} of {}", proved.start.height, proved.head.height, proved.head.chain_id);
+                    let name = format!("{}_{}_range_proof.json", proved.start.height, proved.head.height);
+                    sync::write_proof(&cli.out_dir.join(name), &proof)?;
+                }
+            }
+        }
+        Command::Resume { proof, execute_only } => {
+            let client = ProverClient::new();
+            let (pk, vk) = client.setup(ELF);
+            let (previous, previous_values) = match proof {
+                Some(path) => {
+                    let proof = sync::read_proof(path)?;
+                    let values = sync::resumable_values(&client, &vk, &proof, &cli.headers_dir)
+                        .map_err(|e| anyhow!("cannot resume from {}: {}", path.display(), e))?;
+                    (proof, values)
+                }
+                None => {
+                    let (path, proof, values) = sync::latest_proof(&client, &vk, &cli.out_dir, &cli.headers_dir, None)?
+                        .ok_or_else(|| anyhow!("no valid proof to resume from in {}", cli.out_dir.display()))?;
+                    println!("resuming from {}", path.display());
+                    (proof, values)
+                }
+            };
+            // The program only extends a proof under the policy it was made under
+            sync::ensure_policy(&previous_values, &policy)?;
+            let trusted = history::read_header(&cli.headers_dir, previous_values.head.height)?;
+            let dahs = head_dahs(&cli.headers_dir)?;
+            let headers = dah_headers(headers_after(&cli.headers_dir, previous_values.head.height)?, &dahs, cli.batch_size.get())?;
+            if headers.is_empty() {
+                bail!("no headers after height {} in {}", previous_values.head.height, cli.headers_dir.display());
+            }
+            if *execute_only {
+                let mut range = vec![trusted];
+                range.extend(headers);
+                dry_run::print_reports(&dry_run::execute_range(&client, &vk, &policy, &range, &dahs, cli.batch_size.get())?);
+                return Ok(());
+            }
+            ensure_valid_schedule(&[&[trusted.clone()], &headers[..]].concat(), &policy)?;
+            sync::prove_chain(&client, &pk, &vk, &policy, Some(previous), trusted, &headers, &dahs, cli.batch_size.get(), &cli.out_dir)?;
+        }
+        Command::Verify { proof, program } => {
+            let proof = sync::read_proof(proof)?;
+            let client = ProverClient::new();
+            let elf = match program {
+                Program::Sync => ELF,
+                Program::Ancestry => ancestry::ANCESTRY_ELF,
+                Program::Blob => blob::BLOB_ELF,
+            };
+            let (_, vk) = client.setup(elf);
+            client.verify(&proof, &vk).map_err(|e| anyhow!("proof does not verify: {}", e))?;
+            match program {
+                Program::Sync => {
+                    let values = sync::verified_values(&proof, &vk)?;
+                    println!("proof verifies");
+                    print_sync_values(&values);
+                }
+                Program::Ancestry => {
+                    let values = AncestryPublicValues::decode(&proof.public_values.to_vec())?;
+                    // The ancestry program takes the sync program's key from its input
+                    let (_, sync_vk) = client.setup(ELF);
+                    if values.sync_vkey_hash != sync::vkey_hash(&sync_vk) {
+                        bail!("proof is not of an ancestor of a sync program proof");
+                    }
+                    println!("proof verifies");
+                    print_ancestry_values(&values);
+                }
+                Program::Blob => {
+                    let values = BlobInclusionPublicValues::decode(&proof.public_values.to_vec())?;
+                    // The blob inclusion program takes the sync program's key from its input
+                    let (_, sync_vk) = client.setup(ELF);
+                    if values.sync_vkey_hash != sync::vkey_hash(&sync_vk) {
+                        bail!("proof is not of a blob under the head of a sync program proof");
+                    }
+                    println!("proof verifies");
+                    print_blob_values(&values);
+                }
+            }
+        }
+        Command::Inspect { proof } => {
+            let public_values = sync::read_proof(proof)?.public_values.to_vec();
+            if let Ok(values) = SyncPublicValues::decode(&public_values) {
+                print_sync_values(&values);
+            } else if let Ok(values) = BlobInclusionPublicValues::decode(&public_values) {
+                print_blob_values(&values);
+            } else {
+                print_ancestry_values(&AncestryPublicValues::decode(&public_values)?);
+            }
+        }
+        Command::Inclusion { proof, height } => {
+            let sync_proof = sync::read_proof(proof)?;
+            let public_values = sync::accepted_values(&sync_proof)?;
+            let inclusion = history::prove_inclusion(&public_values, &cli.headers_dir, *height)?;
+            if !history::verify_inclusion(&public_values, &inclusion) {
+                bail!("inclusion proof of height {} does not verify", height);
+            }
+            let path = cli.out_dir.join(format!("{}_inclusion.json", height));
+            fs::write(&path, serde_json::to_string(&inclusion)?)?;
+            println!("wrote {}", path.display());
+        }
+        Command::Ancestry { proof, height } => {
+            let sync_proof = sync::read_proof(proof)?;
+            let public_values = sync::accepted_values(&sync_proof)?;
+            let store = HeaderStore::open(&cli.store, &public_values.head.chain_id)?;
+            let rpc = cli.optional_rpc_client()?;
+            let headers = ancestry::fetch_ancestry(&store, rpc.as_ref(), public_values.head.height, *height).await?;
+            let client = ProverClient::new();
+            let (_, vk) = client.setup(ELF);
+            let ancestry_proof = ancestry::prove_ancestry(&client, &vk, &sync_proof, &headers)?;
+            sync::write_proof(&cli.out_dir.join(format!("{}_ancestry_proof.json", height)), &ancestry_proof)?;
+        }
+        Command::Blob { proof, blob } => {
+            let sync_proof = sync::read_proof(proof)?;
+            let blob = blob::read_blob(blob)?;
+            let client = ProverClient::new();
+            let (_, vk) = client.setup(ELF);
+            let blob_proof = blob::prove_blob(&client, &vk, &sync_proof, &blob)?;
+            let height = sync::accepted_values(&sync_proof)?.head.height;
+            sync::write_proof(&cli.out_dir.join(format!("{}_blob_proof.json", height)), &blob_proof)?;
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,187 @@
+//! Fetching from several RPC endpoints and only accepting what a quorum of them agrees on.
+use crate::source::{Endpoint, HeaderSource};
+use anyhow::{bail, Result};
+use std::fmt::Debug;
+use tendermint::block::Header;
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// A set of endpoints, of which `quorum` must return the same answer for it to be accepted.
+///
+/// Endpoints are asked in order until a quorum of them answered; endpoints that fail, for example
+/// because they lag behind and do not have the height yet, are skipped. Any disagreement among the
+/// answers is refused rather than outvoted, since it means that some endpoint is lying.
+pub struct QuorumClient {
+    endpoints: Vec<Endpoint>,
+    quorum: usize,
+}
+
+impl QuorumClient {
+    pub fn new(endpoints: Vec<Endpoint>, quorum: usize) -> Result<Self> {
+        if quorum == 0 || quorum > endpoints.len() {
+            bail!("quorum of {} is not possible with {} endpoints", quorum, endpoints.len());
+        }
+        Ok(QuorumClient { endpoints, quorum })
+    }
+
+    /// Check that every answer has the same `key`, returning the first answer.
+    fn agree<T, K: PartialEq + Debug>(
+        &self,
+        what: &str,
+        answers: Vec<(&str, T)>,
+        errors: Vec<String>,
+        key: impl Fn(&T) -> K,
+    ) -> Result<T> {
+        if answers.len() < self.quorum {
+            bail!(
+                "only {} of the {} endpoints needed returned {}: {}",
+                answers.len(),
+                self.quorum,
+                what,
+                errors.join("; ")
+            );
+        }
+        let expected = key(&answers[0].1);
+        for (url, answer) in &answers[1..] {
+            let found = key(answer);
+            if found != expected {
+                bail!(
+                    "endpoints disagree on {}: {} returned {:?}, {} returned {:?}",
+                    what,
+                    answers[0].0,
+                    expected,
+                    url,
+                    found
+                );
+            }
+        }
+        Ok(answers.into_iter().next().expect("quorum is positive").1)
+    }
+}
+
+impl HeaderSource for QuorumClient {
+    async fn fetch_chain_id(&self) -> Result<String> {
+        let (mut answers, mut errors) = (vec![], vec![]);
+        for endpoint in &self.endpoints {
+            if answers.len() == self.quorum {
+                break;
+            }
+            match endpoint.fetch_chain_id().await {
+                Ok(chain_id) => answers.push((endpoint.url(), chain_id)),
+                Err(e) => errors.push(format!("{}: {}", endpoint.url(), e)),
+            }
+        }
+        self.agree("the chain id", answers, errors, |chain_id| chain_id.clone())
+    }
+
+    /// The endpoints must agree on the header and on both validator sets; the commit signatures
+    /// are checked by the light client verification itself.
+    async fn fetch_light_block(&self, height: u64) -> Result<LightBlock> {
+        let (mut answers, mut errors) = (vec![], vec![]);
+        for endpoint in &self.endpoints {
+            if answers.len() == self.quorum {
+                break;
+            }
+            match endpoint.fetch_light_block(height).await {
+                Ok(block) => answers.push((endpoint.url(), block)),
+                Err(e) => errors.push(format!("{}: {}", endpoint.url(), e)),
+            }
+        }
+        self.agree(&format!("light block {}", height), answers, errors, |block| {
+            (block.signed_header.header().hash(), block.validators.hash(), block.next_validators.hash())
+        })
+    }
+
+    async fn fetch_header(&self, height: u64) -> Result<Header> {
+        let (mut answers, mut errors) = (vec![], vec![]);
+        for endpoint in &self.endpoints {
+            if answers.len() == self.quorum {
+                break;
+            }
+            match endpoint.fetch_header(height).await {
+                Ok(header) => answers.push((endpoint.url(), header)),
+                Err(e) => errors.push(format!("{}: {}", endpoint.url(), e)),
+            }
+        }
+        self.agree(&format!("header {}", height), answers, errors, |header| header.hash())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{self, FixtureServer},
+        tm_rpc_utils::{
+            tests::{mock_server, quick_config},
+            TendermintRPCClient,
+        },
+    };
+    use std::{fs, path::PathBuf};
+    use tendermint::validator::Set;
+
+    const CELESTIA: &str = r#"{"result":{"node_info":{"id":"0123456789abcdef0123456789abcdef01234567","network":"celestia"}}}"#;
+    const FORK: &str = r#"{"result":{"node_info":{"id":"0123456789abcdef0123456789abcdef01234567","network":"fork"}}}"#;
+
+    async fn quorum_client(responses: Vec<Vec<(u16, &'static str)>>, quorum: usize) -> QuorumClient {
+        let mut clients = vec![];
+        for endpoint_responses in responses {
+            clients.push(Endpoint::CometBft(TendermintRPCClient::with_config(mock_server(endpoint_responses).await, quick_config())));
+        }
+        QuorumClient::new(clients, quorum).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_quorum_skips_failing_endpoints_and_refuses_disagreement() {
+        let client = quorum_client(vec![vec![(404, "{}")], vec![(200, CELESTIA)]], 1).await;
+        assert_eq!(client.fetch_chain_id().await.unwrap(), "celestia");
+
+        let client = quorum_client(vec![vec![(200, CELESTIA)], vec![(200, FORK)]], 2).await;
+        let error = client.fetch_chain_id().await.unwrap_err().to_string();
+        assert!(error.contains("disagree"), "{}", error);
+
+        let client = quorum_client(vec![vec![(200, CELESTIA)], vec![(404, "{}")]], 2).await;
+        assert!(client.fetch_chain_id().await.is_err());
+        assert!(QuorumClient::new(vec![], 1).is_err());
+    }
+
+    /// Serve `block` from a fixture directory named after `name`.
+    async fn serve_light_block(name: &str, block: &LightBlock) -> FixtureServer {
+        let dir = std::env::temp_dir().join(format!("quorum-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fixtures::write_status(&dir, "celestia", [7; 20]).unwrap();
+        fixtures::write_light_block(&dir, block).unwrap();
+        FixtureServer::start(&dir).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_quorum_refuses_light_blocks_that_differ() {
+        let path = PathBuf::from("needed_headers/75845.json");
+        let block: LightBlock = serde_json::from_reader(fs::File::open(&path).unwrap()).unwrap();
+
+        // The same height with another app hash, and so another header hash
+        let mut json: serde_json::Value = serde_json::from_reader(fs::File::open(&path).unwrap()).unwrap();
+        json["signed_header"]["header"]["app_hash"] = serde_json::Value::String("00".repeat(32));
+        let other_header: LightBlock = serde_json::from_value(json).unwrap();
+
+        // The same header with one validator less
+        let mut other_validators = block.clone();
+        other_validators.validators = Set::new(block.validators.validators()[1..].to_vec(), None);
+
+        let honest = serve_light_block("honest", &block).await;
+        let quorum = |servers: [&FixtureServer; 2]| {
+            let endpoints = servers
+                .iter()
+                .map(|server| Endpoint::CometBft(TendermintRPCClient::with_config(server.url().to_string(), quick_config())))
+                .collect();
+            QuorumClient::new(endpoints, 2).unwrap()
+        };
+        let fetched = quorum([&honest, &honest]).fetch_light_block(75845).await.unwrap();
+        assert_eq!(fetched.signed_header.header().hash(), block.signed_header.header().hash());
+
+        for (name, forged) in [("header", other_header), ("validators", other_validators)] {
+            let liar = serve_light_block(name, &forged).await;
+            let error = quorum([&honest, &liar]).fetch_light_block(75845).await.unwrap_err().to_string();
+            assert!(error.contains("disagree on light block 75845"), "{}: {}", name, error);
+        }
+    }
+}
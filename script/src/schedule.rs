@@ -0,0 +1,180 @@
+//! Planning which headers to verify, skipping as far ahead as the trust threshold allows.
+use crate::{source::HeaderSource, store::HeaderStore};
+use anyhow::{bail, Result};
+use cryptographic_sync_common::{Timestamp, TrustPolicy};
+use std::{fs, path::Path};
+use tendermint_light_client_verifier::{
+    options::Options,
+    types::{LightBlock, Time, TrustThreshold},
+    ProdVerifier, Verdict, Verifier,
+};
+
+/// The light client options for a trust policy, as the program builds them.
+pub fn options(policy: &TrustPolicy) -> Result<Options> {
+    Ok(Options {
+        trust_threshold: TrustThreshold::new(policy.trust_threshold_numerator, policy.trust_threshold_denominator)?,
+        trusting_period: policy.trusting_period,
+        clock_drift: policy.clock_drift,
+    })
+}
+
+/// The verification time the program would be given now.
+pub fn now() -> Result<Time> {
+    let now = Timestamp::now();
+    Ok(Time::from_unix_timestamp(now.seconds, now.nanos)?)
+}
+
+/// Find the headers to verify from `trusted` up to `target_height`, ending with the target.
+///
+/// This is the light client's bisection: the target is tried first, and whenever the trusted
+/// validators do not hold enough of its voting power the candidate is moved halfway back towards
+/// the trusted header, until one verifies and becomes the new trusted header. Any other failure,
+/// such as the trusted header expiring, cannot be fixed by skipping less and is returned.
+pub async fn plan_schedule(
+    store: &HeaderStore,
+    client: Option<&impl HeaderSource>,
+    trusted: LightBlock,
+    target_height: u64,
+    policy: &TrustPolicy,
+    now: Time,
+) -> Result<Vec<LightBlock>> {
+    let trusted_height = trusted.height().value();
+    if target_height <= trusted_height {
+        bail!("target {} is not after the trusted height {}", target_height, trusted_height);
+    }
+    let opt = options(policy)?;
+    let vp = ProdVerifier::default();
+
+    let mut schedule = vec![];
+    let mut trusted = trusted;
+    let mut candidate_height = target_height;
+    while trusted.height().value() < target_height {
+        // Bisection comes back to the same heights often, and across runs, so go through the store
+        let candidate = store.get_or_fetch(client, candidate_height).await?;
+
+        match vp.verify_update_header(candidate.as_untrusted_state(), trusted.as_trusted_state(), &opt, now) {
+            Verdict::Success => {
+                println!("{} -> {}", trusted.height(), candidate_height);
+                trusted = candidate.clone();
+                schedule.push(candidate);
+                candidate_height = target_height;
+            }
+            Verdict::NotEnoughTrust(_) => {
+                let trusted_height = trusted.height().value();
+                if candidate_height == trusted_height + 1 {
+                    bail!("header {} does not verify from the adjacent header {}", candidate_height, trusted_height);
+                }
+                candidate_height = trusted_height + (candidate_height - trusted_height) / 2;
+            }
+            Verdict::Invalid(detail) => {
+                bail!("header {} does not verify from {}: {}", candidate_height, trusted.height(), detail);
+            }
+        }
+    }
+    Ok(schedule)
+}
+
+/// A pair of consecutive headers of a schedule that the program would reject.
+#[derive(Debug, Clone)]
+pub struct ScheduleFailure {
+    pub from: u64,
+    pub to: u64,
+    pub reason: String,
+}
+
+/// Check every transition of `headers` natively, as the program will verify them at `now`.
+///
+/// All failing pairs are reported rather than only the first one; after a failure the next
+/// header is still checked from the one that failed, as the program would if it were fixed.
+pub fn validate_schedule(headers: &[LightBlock], policy: &TrustPolicy, now: Time) -> Result<Vec<ScheduleFailure>> {
+    let opt = options(policy)?;
+    let vp = ProdVerifier::default();
+    let failures = headers
+        .windows(2)
+        .filter_map(|pair| {
+            let (trusted, untrusted) = (&pair[0], &pair[1]);
+            let reason = if untrusted.height() <= trusted.height() {
+                Some("height does not increase".to_string())
+            } else if untrusted.signed_header.header().chain_id != trusted.signed_header.header().chain_id {
+                Some("chain id changes".to_string())
+            } else {
+                match vp.verify_update_header(untrusted.as_untrusted_state(), trusted.as_trusted_state(), &opt, now) {
+                    Verdict::Success => None,
+                    Verdict::NotEnoughTrust(tally) => Some(format!("not enough trusted voting power signed: {}", tally)),
+                    Verdict::Invalid(detail) => Some(detail.to_string()),
+                }
+            };
+            reason.map(|reason| ScheduleFailure { from: trusted.height().value(), to: untrusted.height().value(), reason })
+        })
+        .collect();
+    Ok(failures)
+}
+
+/// Write every header of a schedule to `dir` as `<height>.json`.
+pub fn write_schedule(dir: &Path, schedule: &[LightBlock]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    for block in schedule {
+        fs::write(dir.join(format!("{}.json", block.height())), serde_json::to_string(block)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{self, FixtureServer},
+        tm_rpc_utils::{tests::quick_config, TendermintRPCClient},
+    };
+
+    fn header(height: u64) -> LightBlock {
+        let file = fs::File::open(format!("needed_headers/{}.json", height)).unwrap();
+        serde_json::from_reader(file).unwrap()
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("schedule-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_plans_from_fixtures_then_from_the_store() {
+        let dir = temp_dir("fixtures");
+        fixtures::write_status(&dir, "celestia", [7; 20]).unwrap();
+        fixtures::write_light_block(&dir, &header(149318)).unwrap();
+        let server = FixtureServer::start(&dir).await.unwrap();
+        let client = TendermintRPCClient::with_config(server.url().to_string(), quick_config());
+
+        let store = HeaderStore::open(&temp_dir("store"), "celestia").unwrap();
+        let now = Time::parse_from_rfc3339("2023-11-20T19:00:00Z").unwrap();
+        let policy = TrustPolicy::default();
+        let planned = plan_schedule(&store, Some(&client), header(75845), 149318, &policy, now).await.unwrap();
+        let heights: Vec<u64> = planned.iter().map(|h| h.height().value()).collect();
+        assert_eq!(heights, [149318]);
+
+        // The fetched header was cached, so the same plan needs no endpoint
+        drop(server);
+        let replanned = plan_schedule(&store, None::<&TendermintRPCClient>, header(75845), 149318, &policy, now).await.unwrap();
+        assert_eq!(replanned[0].signed_header.header().hash(), planned[0].signed_header.header().hash());
+        assert!(plan_schedule(&store, None::<&TendermintRPCClient>, header(149318), 75845, &policy, now).await.is_err());
+    }
+
+    #[test]
+    fn test_validates_fixture_schedules() {
+        let policy = TrustPolicy::default();
+        let headers = [header(75845), header(149318), header(220495)];
+        let now = Time::parse_from_rfc3339("2023-11-20T19:00:00Z").unwrap();
+        assert!(validate_schedule(&headers[..2], &policy, now).unwrap().is_empty());
+
+        // Twenty days later the first header is out of its two week trusting period
+        let now = Time::parse_from_rfc3339("2023-11-30T10:00:00Z").unwrap();
+        let failures = validate_schedule(&headers, &policy, now).unwrap();
+        assert_eq!(failures.len(), 1, "{:?}", failures);
+        assert_eq!((failures[0].from, failures[0].to), (75845, 149318));
+
+        let backwards = [header(149318), header(75845)];
+        let failures = validate_schedule(&backwards, &policy, now).unwrap();
+        assert_eq!(failures[0].reason, "height does not increase");
+    }
+}
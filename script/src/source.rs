@@ -0,0 +1,93 @@
+//! Where headers are fetched from: a CometBFT RPC or a celestia-node JSON-RPC endpoint.
+use crate::{celestia_node::CelestiaNodeClient, tm_rpc_utils::TendermintRPCClient};
+use anyhow::{anyhow, Result};
+use tendermint::block::Header;
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// A source of headers of one chain.
+pub trait HeaderSource {
+    /// The chain ID of the headers.
+    async fn fetch_chain_id(&self) -> Result<String>;
+
+    /// The light block at `height`, with its commit and both validator sets.
+    async fn fetch_light_block(&self, height: u64) -> Result<LightBlock>;
+
+    /// The header at `height` alone.
+    async fn fetch_header(&self, height: u64) -> Result<Header> {
+        Ok(self.fetch_light_block(height).await?.signed_header.header().clone())
+    }
+}
+
+impl HeaderSource for TendermintRPCClient {
+    async fn fetch_chain_id(&self) -> Result<String> {
+        Ok(TendermintRPCClient::fetch_chain_id(self).await?)
+    }
+
+    async fn fetch_light_block(&self, height: u64) -> Result<LightBlock> {
+        let peer_id = self.peer_id().await?;
+        TendermintRPCClient::fetch_light_block(self, height, peer_id)
+            .await
+            .map_err(|e| anyhow!("could not fetch header {}: {}", height, e))
+    }
+
+    /// Only the commit is fetched, not the validator sets.
+    async fn fetch_header(&self, height: u64) -> Result<Header> {
+        let commit = self
+            .fetch_commit(height)
+            .await
+            .map_err(|e| anyhow!("could not fetch header {}: {}", height, e))?;
+        Ok(commit.result.signed_header.header)
+    }
+}
+
+impl HeaderSource for CelestiaNodeClient {
+    async fn fetch_chain_id(&self) -> Result<String> {
+        Ok(CelestiaNodeClient::fetch_chain_id(self).await?)
+    }
+
+    async fn fetch_light_block(&self, height: u64) -> Result<LightBlock> {
+        CelestiaNodeClient::fetch_light_block(self, height).await
+    }
+
+    async fn fetch_header(&self, height: u64) -> Result<Header> {
+        Ok(self.fetch_extended_header(height).await?.header)
+    }
+}
+
+/// One endpoint of either kind, as configured on the command line.
+pub enum Endpoint {
+    CometBft(TendermintRPCClient),
+    CelestiaNode(CelestiaNodeClient),
+}
+
+impl Endpoint {
+    pub fn url(&self) -> &str {
+        match self {
+            Endpoint::CometBft(client) => client.url(),
+            Endpoint::CelestiaNode(client) => client.url(),
+        }
+    }
+}
+
+impl HeaderSource for Endpoint {
+    async fn fetch_chain_id(&self) -> Result<String> {
+        match self {
+            Endpoint::CometBft(client) => HeaderSource::fetch_chain_id(client).await,
+            Endpoint::CelestiaNode(client) => HeaderSource::fetch_chain_id(client).await,
+        }
+    }
+
+    async fn fetch_light_block(&self, height: u64) -> Result<LightBlock> {
+        match self {
+            Endpoint::CometBft(client) => HeaderSource::fetch_light_block(client, height).await,
+            Endpoint::CelestiaNode(client) => HeaderSource::fetch_light_block(client, height).await,
+        }
+    }
+
+    async fn fetch_header(&self, height: u64) -> Result<Header> {
+        match self {
+            Endpoint::CometBft(client) => HeaderSource::fetch_header(client, height).await,
+            Endpoint::CelestiaNode(client) => HeaderSource::fetch_header(client, height).await,
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! A persistent cache of fetched light blocks, so that every header is fetched at most once.
+use crate::source::HeaderSource;
+use anyhow::{anyhow, ensure, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// Light blocks of one chain, stored as `<root>/<chain id>/<height>.json`.
+pub struct HeaderStore {
+    dir: PathBuf,
+    chain_id: String,
+}
+
+/// Chain IDs that have headers in the store at `root`.
+pub fn cached_chain_ids(root: &Path) -> Result<Vec<String>> {
+    if !root.exists() {
+        return Ok(vec![]);
+    }
+    let mut chain_ids = vec![];
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        if entry.path().is_dir() {
+            chain_ids.extend(entry.file_name().to_str().map(str::to_string));
+        }
+    }
+    Ok(chain_ids)
+}
+
+impl HeaderStore {
+    pub fn open(root: &Path, chain_id: &str) -> Result<Self> {
+        let dir = root.join(chain_id);
+        fs::create_dir_all(&dir)?;
+        Ok(HeaderStore { dir, chain_id: chain_id.to_string() })
+    }
+
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    fn path(&self, height: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", height))
+    }
+
+    /// The cached light block at `height`, if any.
+    pub fn get(&self, height: u64) -> Result<Option<LightBlock>> {
+        let path = self.path(height);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = fs::File::open(&path)?;
+        let block = serde_json::from_reader(file).map_err(|e| anyhow!("could not parse {}: {}", path.display(), e))?;
+        Ok(Some(block))
+    }
+
+    /// Cache a light block, which must be of the store's chain.
+    pub fn put(&self, block: &LightBlock) -> Result<()> {
+        let chain_id = block.signed_header.header().chain_id.to_string();
+        ensure!(
+            chain_id == self.chain_id,
+            "header {} is of chain {}, not {}",
+            block.height(),
+            chain_id,
+            self.chain_id
+        );
+        // Write then rename, so that an interrupted write never leaves a truncated header behind
+        let path = self.path(block.height().value());
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(block)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// The light block at `height`, fetched and cached on a miss.
+    ///
+    /// Without a `client` only cached headers are available, which is enough to work offline
+    /// once every header a command needs has been fetched.
+    pub async fn get_or_fetch(&self, client: Option<&impl HeaderSource>, height: u64) -> Result<LightBlock> {
+        if let Some(block) = self.get(height)? {
+            return Ok(block);
+        }
+        let client = client.ok_or_else(|| anyhow!("header {} is not cached and no RPC endpoint is set", height))?;
+        println!("fetching header {}", height);
+        let block = client.fetch_light_block(height).await?;
+        self.put(&block)?;
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{self, FixtureServer},
+        tm_rpc_utils::{tests::quick_config, TendermintRPCClient},
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn header(height: u64) -> LightBlock {
+        let file = fs::File::open(format!("needed_headers/{}.json", height)).unwrap();
+        serde_json::from_reader(file).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_trips_and_fetches_misses() {
+        let root = temp_dir("root");
+        let store = HeaderStore::open(&root, "celestia").unwrap();
+        let block = header(75845);
+        assert!(store.get(75845).unwrap().is_none());
+        store.put(&block).unwrap();
+        assert_eq!(store.get(75845).unwrap(), Some(block.clone()));
+        assert!(!root.join("celestia/75845.json.tmp").exists(), "the temporary file is renamed");
+        assert_eq!(cached_chain_ids(&root).unwrap(), ["celestia"]);
+        assert!(HeaderStore::open(&root, "mocha-4").unwrap().put(&block).is_err());
+
+        // A hit needs no endpoint, a miss does
+        let offline = None::<&TendermintRPCClient>;
+        assert_eq!(store.get_or_fetch(offline, 75845).await.unwrap(), block);
+        assert!(store.get_or_fetch(offline, 149318).await.is_err());
+
+        let fixtures = temp_dir("fixtures");
+        fixtures::write_status(&fixtures, "celestia", [7; 20]).unwrap();
+        fixtures::write_light_block(&fixtures, &header(149318)).unwrap();
+        let server = FixtureServer::start(&fixtures).await.unwrap();
+        let client = TendermintRPCClient::with_config(server.url().to_string(), quick_config());
+        let fetched = store.get_or_fetch(Some(&client), 149318).await.unwrap();
+        assert_eq!(fetched.signed_header.header().hash(), header(149318).signed_header.header().hash());
+        drop(server);
+        assert_eq!(store.get(149318).unwrap(), Some(fetched), "the fetched header is cached");
+    }
+}
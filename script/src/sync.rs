@@ -0,0 +1,196 @@
+//! Proving header batches one after the other, each proof recursively verifying the previous one.
+use crate::{aggregate, convert::Dahs, history::read_header};
+use anyhow::{anyhow, bail, ensure, Result};
+use cryptographic_sync_common::{DataAvailabilityRoots, ProgramMode, SyncPublicValues, Timestamp, TrustPolicy};
+use sha2::{Digest, Sha256};
+use sp1_sdk::{HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tendermint_light_client_verifier::types::LightBlock;
+
+/// The program input extending `previous`, whose head is `trusted`, with `batch`.
+///
+/// `head_dah` is the data availability header of the batch's last header, which the program
+/// requires when built with its `dah` feature.
+pub fn step_stdin(
+    vk: &SP1VerifyingKey,
+    policy: &TrustPolicy,
+    previous: &SP1ProofWithPublicValues,
+    trusted: &LightBlock,
+    batch: &[LightBlock],
+    head_dah: Option<&DataAvailabilityRoots>,
+) -> Result<SP1Stdin> {
+    let previous_values = SyncPublicValues::decode(&previous.public_values.to_vec())?;
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&vk.hash_u32());
+    stdin.write(&ProgramMode::Sync);
+    stdin.write(&previous.public_values.to_vec());
+    stdin.write_vec(previous_values.start.hash.to_vec());
+    stdin.write(policy);
+    stdin.write(&Timestamp::now());
+    stdin.write_vec(serde_cbor::to_vec(&Some(trusted))?);
+    stdin.write_vec(serde_cbor::to_vec(batch)?);
+    stdin.write(&head_dah);
+    let previous_inner = *match previous.proof.clone() {
+        SP1Proof::Compressed(c) => c,
+        _ => bail!("previous proof is not a compressed proof"),
+    };
+    stdin.write_proof(previous_inner, vk.vk.clone());
+    Ok(stdin)
+}
+
+/// Decode the public values of a proof, failing with the reason if the program rejected it.
+pub fn accepted_values(proof: &SP1ProofWithPublicValues) -> Result<SyncPublicValues> {
+    let values = SyncPublicValues::decode(&proof.public_values.to_vec())?;
+    if let Some(rejection) = &values.rejection {
+        bail!("step rejected: {}", rejection);
+    }
+    Ok(values)
+}
+
+/// The hash a program commits of the verifying key it recursively verifies: the SHA-256 of the
+/// key's words, in the zkVM's little-endian memory order.
+pub fn vkey_hash(vk: &SP1VerifyingKey) -> [u8; 32] {
+    let bytes: Vec<u8> = vk.hash_u32().iter().flat_map(|word| word.to_le_bytes()).collect();
+    Sha256::digest(bytes).into()
+}
+
+/// Decode the public values of a proof of the sync program with the verifying key `vk`, failing
+/// if it was rejected or committed another program's key.
+///
+/// The program takes the key it verifies previous steps with from its input, so a proof that
+/// verifies against `vk` may still extend steps proved by another program; only the committed
+/// key hash tells them apart.
+pub fn verified_values(proof: &SP1ProofWithPublicValues, vk: &SP1VerifyingKey) -> Result<SyncPublicValues> {
+    let values = accepted_values(proof)?;
+    ensure!(values.vkey_hash == vkey_hash(vk), "proof extends steps of another program");
+    Ok(values)
+}
+
+/// Check that a sync proof can be extended from the headers in `headers_dir`, returning its
+/// public values.
+///
+/// The proof must verify against `vk` and pass [`verified_values`], and its head must be the
+/// header of its height in `headers_dir`, as the next step starts from that file.
+pub fn resumable_values(
+    client: &ProverClient,
+    vk: &SP1VerifyingKey,
+    proof: &SP1ProofWithPublicValues,
+    headers_dir: &Path,
+) -> Result<SyncPublicValues> {
+    let values = verified_values(proof, vk)?;
+    let header = read_header(headers_dir, values.head.height)?;
+    ensure!(
+        header.signed_header.header().hash().as_bytes() == values.head.hash,
+        "the header file of height {} is another header than the proved head",
+        values.head.height
+    );
+    client.verify(proof, vk).map_err(|e| anyhow!("proof does not verify: {}", e))?;
+    Ok(values)
+}
+
+/// Fail unless `values` were proved under `policy`, as the program refuses to extend a proof
+/// under another one.
+pub fn ensure_policy(values: &SyncPublicValues, policy: &TrustPolicy) -> Result<()> {
+    ensure!(
+        values.policy == *policy,
+        "the proof was made under the trust policy {:?}, not {:?}",
+        values.policy,
+        policy
+    );
+    Ok(())
+}
+
+/// Read a proof written by [`write_proof`].
+pub fn read_proof(path: &Path) -> Result<SP1ProofWithPublicValues> {
+    let file = fs::File::open(path).map_err(|e| anyhow!("could not open {}: {}", path.display(), e))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Write a proof as JSON.
+pub fn write_proof(path: &Path, proof: &SP1ProofWithPublicValues) -> Result<()> {
+    fs::write(path, serde_json::to_string(proof)?)?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+/// The proof of the highest head in `out_dir` that can be resumed from.
+///
+/// Every `*_proof.json` file is a candidate, newest head first; the first one that starts from
+/// `start` if given and passes [`resumable_values`] is returned along with its path and public
+/// values. Proofs of other programs, stale circuits, other chains or corrupted files are skipped.
+pub fn latest_proof(
+    client: &ProverClient,
+    vk: &SP1VerifyingKey,
+    out_dir: &Path,
+    headers_dir: &Path,
+    start: Option<&[u8; 32]>,
+) -> Result<Option<(PathBuf, SP1ProofWithPublicValues, SyncPublicValues)>> {
+    let mut candidates = vec![];
+    for entry in fs::read_dir(out_dir)? {
+        let path = entry?.path();
+        let is_proof = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.ends_with("_proof.json"));
+        if !is_proof {
+            continue;
+        }
+        let Ok(proof) = read_proof(&path) else { continue };
+        let Ok(values) = verified_values(&proof, vk) else { continue };
+        if start.map_or(true, |start| values.start.hash == *start) {
+            candidates.push((values.head.height, path, proof));
+        }
+    }
+    candidates.sort_by_key(|(height, _, _)| std::cmp::Reverse(*height));
+
+    for (_, path, proof) in candidates {
+        match resumable_values(client, vk, &proof, headers_dir) {
+            Ok(values) => return Ok(Some((path, proof, values))),
+            Err(e) => println!("skipping {}: {}", path.display(), e),
+        }
+    }
+    Ok(None)
+}
+
+/// Extend the chain of proofs by `headers`, `batch_size` headers per step.
+///
+/// Without a `previous` proof the chain starts with a proof of the first batch from `trusted`,
+/// which becomes the trusted start checkpoint. The head of each step takes its data availability
+/// header from `dahs`, if there. Every step's proof is written to
+/// `<out_dir>/<height>_proof.json`, so that an interrupted run can be resumed from the last one.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_chain(
+    client: &ProverClient,
+    pk: &SP1ProvingKey,
+    vk: &SP1VerifyingKey,
+    policy: &TrustPolicy,
+    previous: Option<SP1ProofWithPublicValues>,
+    trusted: LightBlock,
+    headers: &[LightBlock],
+    dahs: &Dahs,
+    batch_size: usize,
+    out_dir: &Path,
+) -> Result<SP1ProofWithPublicValues> {
+    let mut running_proof = previous;
+    let mut running_head = trusted;
+    for batch in headers.chunks(batch_size) {
+        let last = batch.last().expect("chunks are not empty");
+        println!("creating proof for {} to {}", batch[0].height(), last.height());
+        let head_dah = dahs.get(&last.height().value());
+        let stdin = match &running_proof {
+            Some(previous) => step_stdin(vk, policy, previous, &running_head, batch, head_dah)?,
+            None => {
+                let mut segment = vec![running_head.clone()];
+                segment.extend_from_slice(batch);
+                aggregate::segment_stdin(vk, policy, &segment, head_dah, &Timestamp::now())?
+            }
+        };
+        let proof = client.prove(pk, stdin).compressed().run()?;
+        let proved = accepted_values(&proof)?;
+        println!("proved height {} of {} at {:?}", proved.head.height, proved.head.chain_id, proved.head.time);
+        write_proof(&out_dir.join(format!("{}_proof.json", proved.head.height)), &proof)?;
+        running_proof = Some(proof);
+        running_head = last.clone();
+    }
+    running_proof.ok_or_else(|| anyhow!("no headers to prove"))
+}
@@ -18,6 +18,7 @@ pub struct PeerIdWrapper {
 #[derive(Debug, Deserialize)]
 pub struct NodeInfoWrapper {
     pub id: String,
+    pub network: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,7 +1,8 @@
 #![allow(dead_code)]
-use crate::tm_rpc_types::*;
-use reqwest::Client;
-use std::{collections::HashMap, env, error::Error};
+use crate::{fixtures, tm_rpc_types::*};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, env, fmt, future::Future, path::PathBuf, sync::OnceLock, time::Duration};
 use subtle_encoding::hex;
 use tendermint::{
     block::signed_header::SignedHeader,
@@ -10,23 +11,173 @@ use tendermint::{
 };
 use tendermint_light_client_verifier::types::{LightBlock, ValidatorSet};
 
+/// Errors of the Tendermint RPC client.
+#[derive(Debug)]
+pub enum RpcError {
+    /// The request could not be sent or its response could not be read.
+    Request { url: String, source: reqwest::Error },
+    /// The endpoint did not answer within the timeout.
+    Timeout { url: String },
+    /// The endpoint answered with an error status.
+    Status { url: String, status: StatusCode },
+    /// The response body is not the expected JSON.
+    Decode { url: String, reason: String },
+    /// The response decoded but holds an unexpected value.
+    Malformed(String),
+    /// The endpoint answered with a JSON-RPC error.
+    Remote { url: String, message: String },
+}
+
+impl RpcError {
+    /// Whether the request may succeed if it is sent again.
+    fn is_transient(&self) -> bool {
+        match self {
+            RpcError::Request { .. } | RpcError::Timeout { .. } => true,
+            RpcError::Status { status, .. } => status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS,
+            RpcError::Decode { .. } | RpcError::Malformed(_) | RpcError::Remote { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Request { url, source } => write!(f, "request to {} failed: {}", url, source),
+            RpcError::Timeout { url } => write!(f, "request to {} timed out", url),
+            RpcError::Status { url, status } => write!(f, "{} answered {}", url, status),
+            RpcError::Decode { url, reason } => write!(f, "could not decode the response of {}: {}", url, reason),
+            RpcError::Malformed(reason) => write!(f, "malformed response: {}", reason),
+            RpcError::Remote { url, message } => write!(f, "{} answered with an error: {}", url, message),
+        }
+    }
+}
 
+impl std::error::Error for RpcError {}
+
+/// Timeouts and retries of the Tendermint RPC client.
+#[derive(Debug, Clone)]
+pub struct RpcConfig {
+    /// Timeout of a whole request, including reading the response.
+    pub timeout: Duration,
+    /// Timeout of establishing a connection.
+    pub connect_timeout: Duration,
+    /// Number of times a transient failure is retried.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every following one.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        RpcConfig {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Runs `request` until it succeeds, fails with an error that is not transient, or runs out of
+/// retries, backing off exponentially in between.
+pub async fn with_retries<T, F, Fut>(config: &RpcConfig, mut request: F) -> Result<T, RpcError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RpcError>>,
+{
+    let mut backoff = config.initial_backoff;
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            Err(e) if e.is_transient() && attempt < config.max_retries => {
+                attempt += 1;
+                println!("{}, retrying in {:?} ({}/{})", e, backoff, attempt, config.max_retries);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(config.max_backoff);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A reqwest client with the timeouts of `config`.
+pub fn http_client(config: &RpcConfig) -> Client {
+    Client::builder()
+        .timeout(config.timeout)
+        .connect_timeout(config.connect_timeout)
+        .build()
+        .expect("could not build the HTTP client")
+}
 
 pub struct TendermintRPCClient {
     url: String,
+    /// Shared by every request, so that connections to the endpoint are reused.
+    client: Client,
+    config: RpcConfig,
+    /// Directory every response is recorded to as a fixture, if any.
+    record_dir: Option<PathBuf>,
+    /// The peer ID of the node, fetched once.
+    peer_id: OnceLock<[u8; 20]>,
 }
 
 impl Default for TendermintRPCClient {
     fn default() -> Self {
-        TendermintRPCClient {
-            url: env::var("TENDERMINT_RPC_URL").expect("TENDERMINT_RPC_URL not set"),
-        }
+        TendermintRPCClient::new(env::var("TENDERMINT_RPC_URL").expect("TENDERMINT_RPC_URL not set"))
     }
 }
 
 impl TendermintRPCClient {
     pub fn new(url: String) -> Self {
-        TendermintRPCClient { url }
+        Self::with_config(url, RpcConfig::default())
+    }
+
+    pub fn with_config(url: String, config: RpcConfig) -> Self {
+        let client = http_client(&config);
+        TendermintRPCClient { url, client, config, record_dir: None, peer_id: OnceLock::new() }
+    }
+
+    /// Record every successful response to `dir`, to be replayed by a [`FixtureServer`].
+    ///
+    /// [`FixtureServer`]: crate::fixtures::FixtureServer
+    pub fn recording(mut self, dir: PathBuf) -> Self {
+        self.record_dir = Some(dir);
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Sends a GET request for `path` and decodes the JSON response, retrying transient failures
+    /// with exponential backoff.
+    async fn get_json<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T, RpcError> {
+        with_retries(&self.config, || self.try_get_json(path, query)).await
+    }
+
+    async fn try_get_json<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T, RpcError> {
+        let url = format!("{}/{}", self.url, path);
+        let request_error = |source: reqwest::Error| {
+            if source.is_timeout() {
+                RpcError::Timeout { url: url.clone() }
+            } else {
+                RpcError::Request { url: url.clone(), source }
+            }
+        };
+        let response = self.client.get(&url).query(query).send().await.map_err(request_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RpcError::Status { url, status });
+        }
+        let body = response.bytes().await.map_err(request_error)?;
+        if let Some(dir) = &self.record_dir {
+            if let Err(e) = fixtures::record(dir, path, query, &body) {
+                println!("could not record the response of {}: {}", url, e);
+            }
+        }
+        serde_json::from_slice(&body).map_err(|e| RpcError::Decode { url, reason: e.to_string() })
     }
 
     /// Retrieves light blocks for the trusted and target block heights.
@@ -34,30 +185,24 @@ impl TendermintRPCClient {
         &self,
         trusted_block_height: u64,
         target_block_height: u64,
-    ) -> (LightBlock, LightBlock) {
-        let peer_id = self.fetch_peer_id().await.unwrap();
+    ) -> Result<(LightBlock, LightBlock), RpcError> {
+        let peer_id = self.fetch_peer_id().await?;
 
-        let trusted_light_block = self
-            .fetch_light_block(trusted_block_height, peer_id)
-            .await
-            .expect("Failed to generate light block 1");
-        let target_light_block = self
-            .fetch_light_block(target_block_height, peer_id)
-            .await
-            .expect("Failed to generate light block 2");
-        (trusted_light_block, target_light_block)
+        let trusted_light_block = self.fetch_light_block(trusted_block_height, peer_id).await?;
+        let target_light_block = self.fetch_light_block(target_block_height, peer_id).await?;
+        Ok((trusted_light_block, target_light_block))
     }
 
     /// Retrieves the latest block height from the Tendermint node.
-    pub async fn get_latest_block_height(&self) -> u64 {
-        let latest_commit = self.fetch_latest_commit().await.unwrap();
-        latest_commit.result.signed_header.header.height.value()
+    pub async fn get_latest_block_height(&self) -> Result<u64, RpcError> {
+        let latest_commit = self.fetch_latest_commit().await?;
+        Ok(latest_commit.result.signed_header.header.height.value())
     }
 
     /// Retrieves the block height from a given block hash.
-    pub async fn get_block_height_from_hash(&self, hash: &[u8]) -> u64 {
-        let block = self.fetch_block_by_hash(hash).await.unwrap();
-        block.result.block.header.height.value()
+    pub async fn get_block_height_from_hash(&self, hash: &[u8]) -> Result<u64, RpcError> {
+        let block = self.fetch_block_by_hash(hash).await?;
+        Ok(block.result.block.header.height.value())
     }
 
     /// Sorts the signatures in the signed header based on the descending order of validators' power.
@@ -86,110 +231,95 @@ impl TendermintRPCClient {
     }
 
     /// Fetches the peer ID from the Tendermint node.
-    pub async fn fetch_peer_id(&self) -> Result<[u8; 20], Box<dyn Error>> {
-        let client = Client::new();
-        let fetch_peer_id_url = format!("{}/status", self.url);
+    pub async fn fetch_peer_id(&self) -> Result<[u8; 20], RpcError> {
+        let response: PeerIdResponse = self.get_json("status", &[]).await?;
+        let id = response.result.node_info.id;
+        hex::decode(&id)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| RpcError::Malformed(format!("peer id {} is not 20 hex encoded bytes", id)))
+    }
 
-        let response: PeerIdResponse = client
-            .get(fetch_peer_id_url)
-            .send()
-            .await?
-            .json::<PeerIdResponse>()
-            .await?;
+    /// The peer ID of the Tendermint node, fetched on the first call.
+    pub async fn peer_id(&self) -> Result<[u8; 20], RpcError> {
+        if let Some(peer_id) = self.peer_id.get() {
+            return Ok(*peer_id);
+        }
+        let peer_id = self.fetch_peer_id().await?;
+        Ok(*self.peer_id.get_or_init(|| peer_id))
+    }
 
-        Ok(hex::decode(response.result.node_info.id)
-            .unwrap()
-            .try_into()
-            .unwrap())
+    /// Fetches the chain ID the Tendermint node is on.
+    pub async fn fetch_chain_id(&self) -> Result<String, RpcError> {
+        let response: PeerIdResponse = self.get_json("status", &[]).await?;
+        Ok(response.result.node_info.network)
     }
 
     /// Fetches a block by its hash.
-    pub async fn fetch_block_by_hash(&self, hash: &[u8]) -> Result<BlockResponse, Box<dyn Error>> {
-        let client = Client::new();
-        let block_by_hash_url = format!(
-            "{}/block_by_hash?hash=0x{}",
-            self.url,
-            String::from_utf8(hex::encode(hash)).unwrap()
-        );
-        let response: BlockResponse = client
-            .get(block_by_hash_url)
-            .send()
-            .await?
-            .json::<BlockResponse>()
-            .await?;
-        Ok(response)
+    pub async fn fetch_block_by_hash(&self, hash: &[u8]) -> Result<BlockResponse, RpcError> {
+        let hash = String::from_utf8(hex::encode(hash)).expect("hex is valid UTF-8");
+        self.get_json("block_by_hash", &[("hash", format!("0x{}", hash))]).await
     }
 
     /// Fetches a light block by its hash.
-    pub async fn get_light_block_by_hash(&self, hash: &[u8]) -> LightBlock {
-        let block = self.fetch_block_by_hash(hash).await.unwrap();
-        let peer_id = self.fetch_peer_id().await.unwrap();
-        self.fetch_light_block(
-            block.result.block.header.height.value(),
-            hex::decode(peer_id).unwrap().try_into().unwrap(),
-        )
-        .await
-        .unwrap()
+    pub async fn get_light_block_by_hash(&self, hash: &[u8]) -> Result<LightBlock, RpcError> {
+        let block = self.fetch_block_by_hash(hash).await?;
+        let peer_id = self.fetch_peer_id().await?;
+        self.fetch_light_block(block.result.block.header.height.value(), peer_id).await
     }
 
     /// Fetches the latest commit from the Tendermint node.
-    pub async fn fetch_latest_commit(&self) -> Result<CommitResponse, Box<dyn Error>> {
-        let url = format!("{}/commit", self.url);
-        let client = Client::new();
-
-        let response: CommitResponse = client
-            .get(url)
-            .send()
-            .await?
-            .json::<CommitResponse>()
-            .await?;
-        Ok(response)
+    pub async fn fetch_latest_commit(&self) -> Result<CommitResponse, RpcError> {
+        self.get_json("commit", &[]).await
     }
 
     /// Fetches a commit for a specific block height.
-    pub async fn fetch_commit(&self, block_height: u64) -> Result<CommitResponse, Box<dyn Error>> {
-        let url = format!("{}/{}", self.url, "commit");
-
-        let client = Client::new();
-
-        let response: CommitResponse = client
-            .get(url)
-            .query(&[
-                ("height", block_height.to_string().as_str()),
-                ("per_page", "100"), // helpful only when fetching validators
-            ])
-            .send()
-            .await?
-            .json::<CommitResponse>()
-            .await?;
-        Ok(response)
+    pub async fn fetch_commit(&self, block_height: u64) -> Result<CommitResponse, RpcError> {
+        self.get_json(
+            "commit",
+            &[
+                ("height", block_height.to_string()),
+                ("per_page", "100".to_string()), // helpful only when fetching validators
+            ],
+        )
+        .await
     }
 
     /// Fetches validators for a specific block height.
-    pub async fn fetch_validators(&self, block_height: u64) -> Result<Vec<Info>, Box<dyn Error>> {
-        let url = format!("{}/{}", self.url, "validators");
+    pub async fn fetch_validators(&self, block_height: u64) -> Result<Vec<Info>, RpcError> {
+        let parse = |name: &str, value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| RpcError::Malformed(format!("validator {} {:?} is not a number", name, value)))
+        };
 
-        let client = Client::new();
         let mut validators = vec![];
         let mut collected_validators = 0;
         let mut page_index = 1;
         loop {
-            let response = client
-                .get(&url)
-                .query(&[
-                    ("height", block_height.to_string().as_str()),
-                    ("per_page", "100"),
-                    ("page", page_index.to_string().as_str()),
-                ])
-                .send()
-                .await?
-                .json::<ValidatorSetResponse>()
+            let response: ValidatorSetResponse = self
+                .get_json(
+                    "validators",
+                    &[
+                        ("height", block_height.to_string()),
+                        ("per_page", "100".to_string()),
+                        ("page", page_index.to_string()),
+                    ],
+                )
                 .await?;
             let block_validator_set: BlockValidatorSet = response.result;
+            let count = parse("count", &block_validator_set.count)?;
+            let total = parse("total", &block_validator_set.total)?;
+            if count == 0 && collected_validators < total {
+                return Err(RpcError::Malformed(format!(
+                    "validators page {} of height {} is empty",
+                    page_index, block_height
+                )));
+            }
             validators.extend(block_validator_set.validators);
-            collected_validators += block_validator_set.count.parse::<i32>().unwrap();
+            collected_validators += count;
 
-            if collected_validators >= block_validator_set.total.parse::<i32>().unwrap() {
+            if collected_validators >= total {
                 break;
             }
             page_index += 1;
@@ -199,11 +329,7 @@ impl TendermintRPCClient {
     }
 
     /// Fetches a light block for a specific block height and peer ID.
-    pub async fn fetch_light_block(
-        &self,
-        block_height: u64,
-        peer_id: [u8; 20],
-    ) -> Result<LightBlock, Box<dyn Error>> {
+    pub async fn fetch_light_block(&self, block_height: u64, peer_id: [u8; 20]) -> Result<LightBlock, RpcError> {
         let commit_response = self.fetch_commit(block_height).await?;
         let mut signed_header = commit_response.result.signed_header;
 
@@ -223,3 +349,103 @@ impl TendermintRPCClient {
         ))
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serve the given `(status, body)` responses in order, one per request, and return the URL.
+    pub(crate) async fn mock_server(responses: Vec<(u16, &'static str)>) -> String {
+        let mut responses = responses.into_iter();
+        mock_server_with(move |_| responses.next().map(|(status, body)| (status, body.to_string()))).await
+    }
+
+    /// Answer each request, headers and body, with the `(status, body)` that `respond` returns
+    /// for it, until it returns `None`, and return the URL.
+    pub(crate) async fn mock_server_with(
+        mut respond: impl FnMut(&str) -> Option<(u16, String)> + Send + 'static,
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                // Read the headers, then as much body as they announce
+                let mut request = vec![];
+                let mut buffer = [0u8; 4096];
+                loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    let Some((headers, sent)) = text.split_once("\r\n\r\n") else { continue };
+                    let length = headers
+                        .lines()
+                        .find_map(|line| line.to_lowercase().strip_prefix("content-length: ").map(str::to_string))
+                        .map_or(0, |length| length.trim().parse().unwrap());
+                    if sent.len() >= length {
+                        break;
+                    }
+                }
+                let Some((status, body)) = respond(&String::from_utf8_lossy(&request)) else { break };
+                let response = format!(
+                    "HTTP/1.1 {} MOCK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    pub(crate) fn quick_config() -> RpcConfig {
+        RpcConfig {
+            timeout: Duration::from_secs(2),
+            connect_timeout: Duration::from_secs(2),
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(20),
+        }
+    }
+
+    const STATUS: &str = r#"{"result":{"node_info":{"id":"0123456789abcdef0123456789abcdef01234567","network":"celestia"}}}"#;
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let url = mock_server(vec![(503, "{}"), (429, "{}"), (200, STATUS)]).await;
+        let client = TendermintRPCClient::with_config(url, quick_config());
+        let peer_id = client.fetch_peer_id().await.unwrap();
+        assert_eq!(peer_id[..2], [0x01, 0x23]);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_on_errors() {
+        let url = mock_server(vec![(404, "{}")]).await;
+        let client = TendermintRPCClient::with_config(url, quick_config());
+        assert!(matches!(client.fetch_chain_id().await, Err(RpcError::Status { status, .. }) if status == 404));
+
+        let url = mock_server(vec![(503, "{}"), (503, "{}"), (503, "{}")]).await;
+        let client = TendermintRPCClient::with_config(url, quick_config());
+        assert!(matches!(client.fetch_chain_id().await, Err(RpcError::Status { status, .. }) if status == 503));
+
+        let url = mock_server(vec![(200, r#"{"result":{"node_info":{"id":"zz","network":"celestia"}}}"#)]).await;
+        let client = TendermintRPCClient::with_config(url, quick_config());
+        assert!(matches!(client.fetch_peer_id().await, Err(RpcError::Malformed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_validator_counts_are_checked() {
+        let page = r#"{"result":{"block_height":"1","validators":[],"count":"many","total":"1"}}"#;
+        let url = mock_server(vec![(200, page)]).await;
+        let client = TendermintRPCClient::with_config(url, quick_config());
+        assert!(matches!(client.fetch_validators(1).await, Err(RpcError::Malformed(_))));
+    }
+}